@@ -1,47 +1,59 @@
-use cosmwasm_std::{to_json_binary, Uint256};
+use cosmwasm_std::{to_json_binary, HexBinary, Uint256};
 use cosmwasm_std::{
     entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    CosmosMsg, BankMsg, Uint128,
 };
 use cw2::set_contract_version;
+use semver::Version;
 
+use shared::asset::{Asset, AssetInfo};
+use shared::fee::withdrawal_msgs;
 use shared::types::Immutables;
-use shared::types::TimelockStage;
+use shared::types::{EscrowStatus, TimelockStage};
 use shared::error::ContractError;
-use interfaces::escrow_dst::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{IMMUTABLE_HASH, SAFETY_DEPOSIT_TOKEN};
+use interfaces::escrow_dst::{BalanceResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{
+    FACTORY, FEE_TREASURY, IMMUTABLES, IMMUTABLE_HASH, LEGACY_IMMUTABLE_HASH, SAFETY_DEPOSIT_TOKEN,
+    STATUS,
+};
 use crate::RESCUE_DELAY;
 
-use shared::utils::{validate_secret};
-use serde::{Deserialize, Serialize};
+use shared::escrow::{resolve_immutables, update_status};
+use shared::migrate::check_migration;
+use shared::utils::{get_code_hash, validate_secret};
 
 const CONTRACT_NAME: &str = "crates.io:escrow-dst";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Fee structure decoded from immutables parameters
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
-pub struct FeeInfo {
-    pub protocol_fee_amount: Uint128,
-    pub integrator_fee_amount: Uint128,
-    pub protocol_fee_recipient: String,
-    pub integrator_fee_recipient: String,
-}
-
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    FACTORY.save(deps.storage, &info.sender)?;
+
     SAFETY_DEPOSIT_TOKEN.save(deps.storage, &msg.safety_deposit_denom)?;
 
     IMMUTABLE_HASH.save(deps.storage, &msg.immutable_hash)?;
 
+    if let Some(immutables) = msg.immutables {
+        if immutables.compute_immutables_hash()? != msg.immutable_hash {
+            return Err(ContractError::InvalidImmutables {});
+        }
+        IMMUTABLES.save(deps.storage, &immutables)?;
+    }
+
     RESCUE_DELAY.save(deps.storage, &msg.rescue_delay)?;
 
+    if let Some(fee_treasury) = msg.fee_treasury {
+        FEE_TREASURY.save(deps.storage, &deps.api.addr_validate(&fee_treasury)?)?;
+    }
+
+    STATUS.save(deps.storage, &EscrowStatus::Active)?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate"))
 }
@@ -54,10 +66,22 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Withdraw { secret, immutables } => withdraw(deps, env, info, secret, immutables),
-        ExecuteMsg::PublicWithdraw { secret, immutables } => public_withdraw(deps, env, info, secret, immutables),
-        ExecuteMsg::Cancel { immutables } => cancel(deps, env, info, immutables),
-        ExecuteMsg::RescueFunds { token, amount,immutables } => rescue_funds(deps, env, info, token, amount,immutables),
+        ExecuteMsg::Withdraw { secret, immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            withdraw(deps, env, info, secret, immutables)
+        }
+        ExecuteMsg::PublicWithdraw { secret, immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            public_withdraw(deps, env, info, secret, immutables)
+        }
+        ExecuteMsg::Cancel { immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            cancel(deps, env, info, immutables)
+        }
+        ExecuteMsg::RescueFunds { token, amount,immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            rescue_funds(deps, env, info, token, amount,immutables)
+        }
     }
 }
 
@@ -68,7 +92,7 @@ pub fn withdraw(
     secret: Binary,
     immutables: Immutables,
 ) -> Result<Response, ContractError> {
-    if info.sender.to_string() != immutables.taker {
+    if info.sender != immutables.taker {
         return Err(ContractError::Unauthorized {});
     }
 
@@ -109,35 +133,29 @@ pub fn cancel(
     info: MessageInfo,
     immutables: Immutables,
 ) -> Result<Response, ContractError> {
-    if info.sender.to_string() != immutables.taker {
+    if info.sender != immutables.taker {
         return Err(ContractError::Unauthorized {});
     }
     
-    validate_immutables(deps.as_ref(), &immutables)?;
-    
     // Check if cancellation timelock has been reached
     let cancellation_time = immutables.timelocks.get_timelock(TimelockStage::DstCancellation);
     if env.block.time.seconds() < cancellation_time {
         return Err(ContractError::TimelockNotReached {});
     }
 
+    update_status(deps.storage, EscrowStatus::Cancelled)?;
+
     let mut messages = vec![];
 
-    messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: immutables.taker,
-            amount: vec![cosmwasm_std::Coin {
-                denom: immutables.token.clone(),
-                amount: immutables.amount.try_into().map_err(|_| ContractError::UintConversionFailed{})?,
-            }],
-        }));
-
-    messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![cosmwasm_std::Coin {
-                denom: SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
-                amount: immutables.safety_deposit.try_into().map_err(|_| ContractError::UintConversionFailed{})?,
-            }],
-        }));
+    if immutables.amount > Uint256::zero() {
+        let token = AssetInfo::from_token(deps.api, &immutables.token);
+        messages.push(Asset::from_uint256(token, immutables.amount)?.transfer_msg(&immutables.taker)?);
+    }
+
+    if immutables.safety_deposit > Uint256::zero() {
+        let safety_deposit = AssetInfo::native(SAFETY_DEPOSIT_TOKEN.load(deps.storage)?);
+        messages.push(Asset::from_uint256(safety_deposit, immutables.safety_deposit)?.transfer_msg(&info.sender)?);
+    }
     
     Ok(Response::new()
         .add_messages(messages)
@@ -152,25 +170,20 @@ pub fn rescue_funds(
     amount: Uint256,
     immutables: Immutables,
 ) -> Result<Response, ContractError> {
-    if info.sender.to_string() != immutables.taker {
+    if info.sender != immutables.taker {
         return Err(ContractError::Unauthorized {});
     }
     
-    validate_immutables(deps.as_ref(), &immutables)?;
-    
     if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::RescueDelay(RESCUE_DELAY.load(deps.storage)?)) {
         return Err(ContractError::TimelockNotReached {});
     }
 
+    update_status(deps.storage, EscrowStatus::Rescued)?;
+
     let mut messages = vec![];
 
-    messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: immutables.taker,
-            amount: vec![cosmwasm_std::Coin {
-                denom: token,
-                amount: amount.try_into().map_err(|_| ContractError::UintConversionFailed{})?,
-            }],
-        }));
+    let token = AssetInfo::from_token(deps.api, &token);
+    messages.push(Asset::from_uint256(token, amount)?.transfer_msg(&immutables.taker)?);
     
     Ok(Response::new()
         .add_messages(messages)
@@ -178,21 +191,80 @@ pub fn rescue_funds(
 }
 
 #[entry_point]
-pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
-    return to_json_binary("no query")
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = check_migration(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    migrate_state(deps.branch(), &env, &previous)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", previous.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
 }
 
-fn validate_immutables(
-    deps: Deps,
-    immutables: &Immutables,
-) -> Result<(), ContractError> {    
-    let immutables_hash = immutables.compute_immutables_hash()?;
-    if IMMUTABLE_HASH.load(deps.storage)? != immutables_hash {
-        return Err(ContractError::InvalidImmutables{});
+/// State migrations, applied in order for every version newer than `previous`
+fn migrate_state(deps: DepsMut, env: &Env, previous: &Version) -> Result<(), ContractError> {
+    // 0.2.0 added the escrow status
+    if *previous < Version::new(0, 2, 0) && !STATUS.exists(deps.storage) {
+        STATUS.save(deps.storage, &EscrowStatus::Active)?;
+    }
+    // 0.2.0 stores the factory, which instantiated the escrow
+    if *previous < Version::new(0, 2, 0) && !FACTORY.exists(deps.storage) {
+        let creator = deps
+            .querier
+            .query_wasm_contract_info(&env.contract.address)?
+            .creator;
+        FACTORY.save(deps.storage, &deps.api.addr_validate(&creator)?)?;
+    }
+    // 0.2.0 hashes immutables with Solidity ABI encoding, older escrows keep
+    // validating against the bincode hash they were created with
+    if *previous < Version::new(0, 2, 0) {
+        LEGACY_IMMUTABLE_HASH.save(deps.storage, &true)?;
     }
     Ok(())
 }
 
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::QueryFactoryAddress {} => to_json_binary(&FACTORY.load(deps.storage)?),
+        QueryMsg::QueryEscrowDstCodeHash {} => to_json_binary(&query_code_hash(deps, env)?),
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Balance {} => to_json_binary(&query_balance(deps, env)?),
+        QueryMsg::Status {} => to_json_binary(&STATUS.load(deps.storage)?),
+    }
+}
+
+fn query_code_hash(deps: Deps, env: Env) -> StdResult<HexBinary> {
+    let contract_info = deps.querier.query_wasm_contract_info(env.contract.address)?;
+    get_code_hash(deps.querier, contract_info.code_id)
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    Ok(ConfigResponse {
+        safety_deposit_denom: SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
+        rescue_delay: RESCUE_DELAY.load(deps.storage)?,
+        immutable_hash: IMMUTABLE_HASH.load(deps.storage)?,
+    })
+}
+
+fn query_balance(deps: Deps, env: Env) -> StdResult<BalanceResponse> {
+    let token = match IMMUTABLES.may_load(deps.storage)? {
+        Some(immutables) => {
+            let info = AssetInfo::from_token(deps.api, &immutables.token);
+            let amount = info.query_balance(&deps.querier, &env.contract.address)?;
+            Some(Asset::new(info, amount))
+        }
+        None => None,
+    };
+    Ok(BalanceResponse {
+        balances: deps.querier.query_all_balances(&env.contract.address)?,
+        token,
+    })
+}
+
 /// Internal withdraw function that handles fee distribution and token transfers
 fn _withdraw(
     deps: DepsMut,
@@ -200,70 +272,102 @@ fn _withdraw(
     secret: Binary,
     immutables: &Immutables,
 ) -> Result<Response, ContractError> {
-    validate_immutables(deps.as_ref(), immutables)?;
-    
     if !validate_secret(&secret, &immutables.hashlock) {
         return Err(ContractError::InvalidSecret {});
     }
-    
-    let fee_info: FeeInfo = serde_json::from_slice(&immutables.parameters)
-        .map_err(|_| ContractError::ErrorFeeParsing{})?;
-    
-    let mut messages = vec![];
-    
-    if fee_info.integrator_fee_amount > Uint128::zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: fee_info.integrator_fee_recipient.clone(),
-            amount: vec![cosmwasm_std::Coin {
-                denom: immutables.token.clone(),
-                amount: fee_info.integrator_fee_amount,
-            }],
-        }));
-    }
-    
-    if fee_info.protocol_fee_amount > Uint128::zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: fee_info.protocol_fee_recipient.clone(),
-            amount: vec![cosmwasm_std::Coin {
-                denom: immutables.token.clone(),
-                amount: fee_info.protocol_fee_amount,
-            }],
-        }));
-    }
-    
-    let total_fees = fee_info.integrator_fee_amount + fee_info.protocol_fee_amount;
-    let remaining_amount = immutables.amount.checked_sub(total_fees.into())
-        .map_err(|_| ContractError::InsufficientEscrowBalance {})?;
-    
-    // Transfer remaining amount to maker
-    if remaining_amount > Uint256::zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: immutables.maker.clone(),
-            amount: vec![cosmwasm_std::Coin {
-                denom: immutables.token.clone(),
-                amount: remaining_amount.try_into().map_err(|_| ContractError::UintConversionFailed{})?,
-            }],
-        }));
-    }
-    
-    // Transfer safety deposit to caller (msg.sender)
-    if immutables.safety_deposit > Uint256::zero() {
-        messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: info.sender.to_string(),
-            amount: vec![cosmwasm_std::Coin {
-                denom: SAFETY_DEPOSIT_TOKEN.load(deps.storage).unwrap(),
-                amount: immutables.safety_deposit.try_into().map_err(|_| ContractError::UintConversionFailed{})?,
-            }],
-        }));
-    }
-    
+
+    update_status(deps.storage, EscrowStatus::Withdrawn)?;
+
+    let (messages, fees) = withdrawal_msgs(
+        deps.api,
+        immutables,
+        FEE_TREASURY.may_load(deps.storage)?.as_ref(),
+        &SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
+        &info.sender,
+    )?;
+
     Ok(Response::new()
         .add_messages(messages)
         .add_attribute("method", "_withdraw")
         .add_attribute("secret", secret.to_string())
         .add_attribute("maker", immutables.maker.clone())
-        .add_attribute("amount", remaining_amount.to_string())
-        .add_attribute("integrator_fee", fee_info.integrator_fee_amount.to_string())
-        .add_attribute("protocol_fee", fee_info.protocol_fee_amount.to_string())
+        .add_attributes(fees.attributes()?)
         .add_attribute("safety_deposit", immutables.safety_deposit.to_string()))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{
+        from_json, Addr, ContractInfoResponse, ContractResult, SystemResult, Timestamp, WasmQuery,
+    };
+    use shared::types::Timelocks;
+    use shared::utils::keccak256;
+
+    #[test]
+    fn test_migrate_from_0_1_0_keeps_legacy_hash() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let factory = deps.api.addr_make("factory");
+        let creator = factory.to_string();
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::ContractInfo { .. } => {
+                let mut info = ContractInfoResponse::default();
+                info.code_id = 1;
+                info.creator = creator.clone();
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&info).unwrap()))
+            }
+            _ => panic!("unexpected query"),
+        });
+        // 0.1.0 state, hashed before the factory set `deployed_at`
+        let mut immutables = Immutables {
+            order_hash: "11".repeat(32),
+            hashlock: keccak256(b"secret"),
+            maker: deps.api.addr_make("maker").to_string(),
+            taker: deps.api.addr_make("resolver").to_string(),
+            token: "uatom".to_string(),
+            amount: Uint256::from(1_000u128),
+            safety_deposit: Uint256::from(100u128),
+            timelocks: Timelocks {
+                deployed_at: 0,
+                src_withdrawal: 10,
+                src_public_withdrawal: 20,
+                src_cancellation: 30,
+                src_public_cancellation: 40,
+                dst_withdrawal: 10,
+                dst_public_withdrawal: 20,
+                dst_cancellation: 30,
+            },
+            parameters: br#"{"protocol_fee_amount":"0","integrator_fee_amount":"0","protocol_fee_recipient":"","integrator_fee_recipient":""}"#.to_vec(),
+            rescue_delay: None,
+        };
+        set_contract_version(deps.as_mut().storage, CONTRACT_NAME, "0.1.0").unwrap();
+        SAFETY_DEPOSIT_TOKEN.save(deps.as_mut().storage, &"ustake".to_string()).unwrap();
+        IMMUTABLE_HASH.save(deps.as_mut().storage, &immutables.compute_legacy_immutables_hash().unwrap()).unwrap();
+        RESCUE_DELAY.save(deps.as_mut().storage, &3600).unwrap();
+
+        migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+        let res = query(deps.as_ref(), env.clone(), QueryMsg::QueryFactoryAddress {}).unwrap();
+        assert_eq!(from_json::<Addr>(&res).unwrap(), factory);
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), EscrowStatus::Active);
+
+        // only the immutables as they were hashed are accepted, and their
+        // timelocks count from the hashed `deployed_at` as in 0.1.0
+        immutables.timelocks.deployed_at = env.block.time.seconds();
+        let withdraw_msg = |immutables: &Immutables| ExecuteMsg::Withdraw {
+            secret: Binary::from(b"secret"),
+            immutables: Some(immutables.clone()),
+        };
+        let info = mock_info(&immutables.taker, &[]);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), withdraw_msg(&immutables)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidImmutables {}));
+
+        immutables.timelocks.deployed_at = 0;
+        let mut env = env;
+        env.block.time = Timestamp::from_seconds(15);
+        let res = execute(deps.as_mut(), env, info, withdraw_msg(&immutables)).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), EscrowStatus::Withdrawn);
+    }
+}
@@ -1,4 +1,7 @@
+use cosmwasm_std::Addr;
 use cw_storage_plus::Item;
-pub const IMMUTABLE_HASH: Item<String> = Item::new("immutable_hash");
+pub use shared::escrow::{IMMUTABLES, IMMUTABLE_HASH, LEGACY_IMMUTABLE_HASH, STATUS};
 pub const SAFETY_DEPOSIT_TOKEN: Item<String> = Item::new("safety_deposit_denom");
 pub const RESCUE_DELAY: Item<u32> = Item::new("rescue_delay");
+pub const FACTORY: Item<Addr> = Item::new("factory");
+pub const FEE_TREASURY: Item<Addr> = Item::new("fee_treasury");
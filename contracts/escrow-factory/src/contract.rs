@@ -1,33 +1,71 @@
 use cosmwasm_std::{
-    entry_point, to_json_binary, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, SubMsg, WasmMsg,
+    entry_point, from_json, to_json_binary, Addr, Binary, Coin, CosmosMsg, Deps, DepsMut, Env,
+    Event, MessageInfo, Order as StorageOrder, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    Uint256, WasmMsg,
 };
+use cw_storage_plus::Bound;
 use cw2::set_contract_version;
-use shared::{validate_token_amounts, ContractError};
+use semver::Version;
+use cw20::Cw20ReceiveMsg;
+use shared::{
+    check_escrow_fees, check_migration, compute_escrow_address, compute_escrow_salt, get_code_hash, hex_to_word,
+    is_valid_partial_fill, validate_rescue_delay, validate_token_amounts, verify_order_signature,
+    Asset, AssetInfo, ContractError, FeeCaps, MerkleFill, Order,
+};
 
-use crate::state::{ESCROW_DST_CODE_ID, SAFETY_DEPOSIT_TOKEN};
-use crate::RESCUE_DELAY;
-use interfaces::escrow_factory::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use shared::types::Immutables;
+use crate::state::{
+    escrows, DEFAULT_RESCUE_DELAY, DEPOSITS, ESCROW_DST_CODE_ID, ESCROW_SRC_CODE_ID, FEE_CAPS,
+    FEE_TREASURY, ORDER_REMAINING, OWNER, PENDING_ESCROW, PENDING_OWNER, RESCUE_DELAY,
+    SAFETY_DEPOSIT_TOKEN, TIMELOCK_WINDOWS, USED_FILLS,
+};
+use interfaces::escrow_factory::{
+    ConfigResponse, EscrowInfo, EscrowKind, EscrowsResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg,
+};
+use shared::types::{Immutables, TimelockStage, TimelockWindows, Timelocks};
 
 const CONTRACT_NAME: &str = "crates.io:escrow-factory";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const INSTANTIATE_ESCROW_REPLY_ID: u64 = 1;
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    OWNER.save(deps.storage, &owner)?;
+
     ESCROW_DST_CODE_ID.save(deps.storage, &msg.escrow_dst_code_id)?;
+    ESCROW_SRC_CODE_ID.save(deps.storage, &msg.escrow_src_code_id)?;
     SAFETY_DEPOSIT_TOKEN.save(deps.storage, &msg.safety_deposit_token)?;
+    let rescue_delay = msg.rescue_delay.unwrap_or(DEFAULT_RESCUE_DELAY);
+    validate_rescue_delay(rescue_delay)?;
+    RESCUE_DELAY.save(deps.storage, &rescue_delay)?;
+    let min_timelock_windows = msg.min_timelock_windows.unwrap_or_default();
+    min_timelock_windows.validate(rescue_delay)?;
+    TIMELOCK_WINDOWS.save(deps.storage, &min_timelock_windows)?;
+    let fee_caps = msg.fee_caps.unwrap_or_default();
+    fee_caps.validate()?;
+    FEE_CAPS.save(deps.storage, &fee_caps)?;
+    if let Some(fee_treasury) = msg.fee_treasury {
+        FEE_TREASURY.save(deps.storage, &deps.api.addr_validate(&fee_treasury)?)?;
+    }
 
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("contract", "escrow-factory"))
+        .add_attribute("contract", "escrow-factory")
+        .add_attribute("owner", owner.to_string()))
 }
 
 #[entry_point]
@@ -41,7 +79,121 @@ pub fn execute(
         ExecuteMsg::CreateEscrowDst {
             mut immutables,
             src_cancellation_timestamp,
-        } => create_escrow_dst(deps, env, info, &mut immutables, src_cancellation_timestamp),
+            packed_timelocks,
+        } => {
+            if let Some(packed_timelocks) = packed_timelocks {
+                immutables.timelocks = Timelocks::from_packed_u256(packed_timelocks);
+            }
+            create_escrow_dst(deps, env, info, &mut immutables, src_cancellation_timestamp)
+        }
+        ExecuteMsg::CreateEscrowSrc {
+            mut immutables,
+            order,
+            signature,
+            maker_pubkey,
+            merkle_fill,
+        } => create_escrow_src(
+            deps,
+            env,
+            info,
+            &mut immutables,
+            *order,
+            signature,
+            maker_pubkey,
+            merkle_fill,
+        ),
+        ExecuteMsg::Deposit {} => deposit(deps, info),
+        ExecuteMsg::WithdrawDeposit { denom, amount } => withdraw_deposit(deps, info, denom, amount),
+        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, env, info, cw20_msg),
+        ExecuteMsg::UpdateConfig {
+            escrow_dst_code_id,
+            escrow_src_code_id,
+            safety_deposit_token,
+            rescue_delay,
+            min_timelock_windows,
+            fee_caps,
+            fee_treasury,
+            clear_fee_treasury,
+        } => update_config(
+            deps,
+            info,
+            escrow_dst_code_id,
+            escrow_src_code_id,
+            safety_deposit_token,
+            rescue_delay,
+            min_timelock_windows,
+            fee_caps,
+            fee_treasury,
+            clear_fee_treasury,
+        ),
+        ExecuteMsg::TransferOwnership { new_owner } => transfer_ownership(deps, info, new_owner),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::MigrateEscrows {
+            immutables_hashes,
+            new_code_id,
+            msg,
+        } => migrate_escrows(deps, info, immutables_hashes, new_code_id, msg),
+    }
+}
+
+/// Handles CW20 tokens sent to the factory with a `ReceiveMsg`
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_json(&cw20_msg.msg)? {
+        ReceiveMsg::CreateEscrowDst {
+            mut immutables,
+            src_cancellation_timestamp,
+            packed_timelocks,
+        } => {
+            if let Some(packed_timelocks) = packed_timelocks {
+                immutables.timelocks = Timelocks::from_packed_u256(packed_timelocks);
+            }
+
+            // the sending CW20 contract is the escrowed token
+            let token = Asset::from_uint256(
+                AssetInfo::from_token(deps.api, &immutables.token),
+                immutables.amount,
+            )?;
+            token.validate_cw20_deposit(&info.sender, cw20_msg.amount)?;
+
+            let resolver = deps.api.addr_validate(&cw20_msg.sender)?;
+            let safety_deposit_token = SAFETY_DEPOSIT_TOKEN.load(deps.storage)?;
+            pull_deposit(deps.storage, &resolver, &safety_deposit_token, immutables.safety_deposit)?;
+
+            let mut funds = vec![];
+            if !immutables.safety_deposit.is_zero() {
+                funds.push(Coin {
+                    denom: safety_deposit_token,
+                    amount: immutables
+                        .safety_deposit
+                        .try_into()
+                        .map_err(|_| ContractError::UintConversionFailed {})?,
+                });
+            }
+
+            let (response, escrow_address) = _create_escrow_dst(
+                deps,
+                env,
+                &mut immutables,
+                src_cancellation_timestamp,
+                funds,
+            )?;
+
+            // the escrow address is known up front, so the tokens can follow the instantiation
+            let mut response = response.add_message(token.transfer_msg(escrow_address)?);
+
+            // anything sent above the escrowed amount goes back to the resolver
+            let excess = cw20_msg.amount - token.amount;
+            if !excess.is_zero() {
+                response = response.add_message(Asset::new(token.info, excess).transfer_msg(resolver)?);
+            }
+
+            Ok(response)
+        }
     }
 }
 
@@ -53,11 +205,51 @@ pub fn create_escrow_dst(
     immutables: &mut Immutables,
     src_cancellation_timestamp: u64,
 ) -> Result<Response, ContractError> {
-    // for verification in escrow dst
-    let immutable_hash = immutables.compute_immutables_hash()?;
+    // Get the safety deposit token from state
+    let safety_deposit_token = SAFETY_DEPOSIT_TOKEN.load(deps.storage)?;
+
+    // Validate all required tokens with sufficient amounts
+    validate_token_amounts(immutables, &info, &safety_deposit_token)?;
+
+    let (response, _) = _create_escrow_dst(
+        deps,
+        env,
+        immutables,
+        src_cancellation_timestamp,
+        info.funds,
+    )?;
+    Ok(response)
+}
+
+/// Validates the destination immutables and instantiates the escrow with `funds`,
+/// returning the response and the escrow address
+fn _create_escrow_dst(
+    deps: DepsMut,
+    env: Env,
+    immutables: &mut Immutables,
+    src_cancellation_timestamp: u64,
+    funds: Vec<Coin>,
+) -> Result<(Response, Addr), ContractError> {
+    // the source chain validates partial fills of the order, so destination
+    // escrows are unique per order hash and hashlock whatever the fill
+    use_fill(deps.storage, immutables, 0)?;
+
+    check_escrow_fees(
+        deps.api,
+        immutables,
+        &FEE_CAPS.load(deps.storage)?,
+        FEE_TREASURY.may_load(deps.storage)?.as_ref(),
+    )?;
+
+    immutables
+        .timelocks
+        .validate(&TIMELOCK_WINDOWS.load(deps.storage)?)?;
 
     immutables.timelocks.set_deployed_at(env.block.time);
 
+    // for verification in escrow dst, covering the deployment time like 1inch
+    let immutable_hash = immutables.compute_immutables_hash()?;
+
     if immutables
         .timelocks
         .get_timelock(shared::TimelockStage::DstCancellation)
@@ -66,36 +258,1229 @@ pub fn create_escrow_dst(
         return Err(ContractError::InvalidCreationTime {});
     }
 
+    // Create instantiate message for escrow
+    let instantiate_msg = to_json_binary(&interfaces::escrow_dst::InstantiateMsg {
+        safety_deposit_denom: SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
+        rescue_delay: escrow_rescue_delay(deps.storage, immutables, TimelockStage::DstCancellation)?,
+        immutable_hash: immutable_hash.clone(),
+        immutables: Some(immutables.clone()),
+        fee_treasury: FEE_TREASURY.may_load(deps.storage)?.map(String::from),
+    })?;
+
+    let (create_escrow_msg, escrow_address) = instantiate_escrow(
+        deps.as_ref(),
+        &env,
+        ESCROW_DST_CODE_ID.load(deps.storage)?,
+        immutables,
+        instantiate_msg,
+        funds,
+        format!("escrow-dst-{}", &immutable_hash[..8]),
+    )?;
+    save_pending_escrow(deps.storage, EscrowKind::Dst, immutable_hash, immutables, &escrow_address)?;
 
-    // Get the safety deposit token from state
-    let safety_deposit_token = SAFETY_DEPOSIT_TOKEN.load(deps.storage).unwrap();
+    let response = Response::new()
+        .add_submessage(create_escrow_msg)
+        .add_attribute("method", "create_escrow_dst")
+        .add_attribute("escrow_address", escrow_address.to_string())
+        .add_attribute("deployed_at", immutables.timelocks.deployed_at.to_string())
+        .add_attribute("hashlock", immutables.hashlock.clone())
+        .add_attribute("taker", immutables.taker.clone());
 
-    // Validate all required tokens with sufficient amounts
-    validate_token_amounts(&immutables, &info, &safety_deposit_token)?;
+    Ok((response, escrow_address))
+}
 
-    // Create instantiate message for escrow
-    let instantiate_msg = to_json_binary(&interfaces::escrow_dst::InstantiateMsg {
-        safety_deposit_denom: safety_deposit_token,
-        rescue_delay: RESCUE_DELAY,
-        immutable_hash: immutable_hash.clone()
+/// Creates a new EscrowSrc contract holding the maker's funds for a signed order
+#[allow(clippy::too_many_arguments)]
+pub fn create_escrow_src(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables: &mut Immutables,
+    order: Order,
+    signature: Binary,
+    maker_pubkey: Binary,
+    merkle_fill: Option<MerkleFill>,
+) -> Result<Response, ContractError> {
+    // only the resolver filling the order can lock the maker's funds
+    if info.sender != immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    order.validate_domain(&env.block.chain_id, &env.contract.address)?;
+
+    if order.is_expired(env.block.time.seconds()) {
+        return Err(ContractError::OrderExpired {});
+    }
+
+    immutables
+        .timelocks
+        .validate(&TIMELOCK_WINDOWS.load(deps.storage)?)?;
+
+    immutables.timelocks.set_deployed_at(env.block.time);
+
+    order.validate_immutables(immutables)?;
+    verify_order_signature(deps.api, &order, &signature, &maker_pubkey)?;
+
+    let remaining_making_amount = ORDER_REMAINING
+        .may_load(deps.storage, &immutables.order_hash)?
+        .unwrap_or(order.making_amount);
+    if immutables.amount.is_zero() || immutables.amount > remaining_making_amount {
+        return Err(ContractError::InvalidPartialFill {});
+    }
+
+    let mut fill_index = 0;
+    if order.allow_multiple_fills {
+        let merkle_fill = merkle_fill.ok_or(ContractError::MissingMerkleProof {})?;
+        fill_index = merkle_fill.index;
+        if merkle_fill.hashlock_info != order.hashlock {
+            return Err(ContractError::OrderMismatch {});
+        }
+        merkle_fill.verify(&immutables.hashlock)?;
+
+        let parts_amount = merkle_fill.parts_amount()?;
+        if parts_amount < 2 {
+            return Err(ContractError::InvalidSecretsAmount {});
+        }
+        if !is_valid_partial_fill(
+            immutables.amount,
+            remaining_making_amount,
+            order.making_amount,
+            parts_amount,
+            merkle_fill.index + 1,
+        ) {
+            return Err(ContractError::InvalidPartialFill {});
+        }
+    }
+
+    let safety_deposit_token = SAFETY_DEPOSIT_TOKEN.load(deps.storage)?;
+
+    // the resolver pays the safety deposit
+    Asset::from_uint256(
+        AssetInfo::native(safety_deposit_token.clone()),
+        immutables.safety_deposit,
+    )?
+    .validate_native_deposit(&info.funds)?;
+
+    use_fill(deps.storage, immutables, fill_index)?;
+
+    ORDER_REMAINING.save(
+        deps.storage,
+        &immutables.order_hash,
+        &(remaining_making_amount - immutables.amount),
+    )?;
+
+    // pull the maker's deposited funds
+    let maker = deps.api.addr_validate(&immutables.maker)?;
+    pull_deposit(deps.storage, &maker, &immutables.token, immutables.amount)?;
+
+    let immutable_hash = immutables.compute_immutables_hash()?;
+
+    let instantiate_msg = to_json_binary(&interfaces::escrow_src::InstantiateMsg {
+        safety_deposit_denom: safety_deposit_token.clone(),
+        rescue_delay: escrow_rescue_delay(deps.storage, immutables, TimelockStage::SrcPublicCancellation)?,
+        immutable_hash: immutable_hash.clone(),
+        immutables: Some(immutables.clone()),
     })?;
 
-    let create_escrow_msg = CosmosMsg::Wasm(WasmMsg::Instantiate {
-        admin: Some(info.sender.to_string()),
-        code_id: ESCROW_DST_CODE_ID.load(deps.storage).unwrap(),
-        msg: instantiate_msg,
-        funds: info.funds,
-        label: format!("escrow-dst-{}", &immutable_hash[..8]),
-    });
+    let (create_escrow_msg, escrow_address) = instantiate_escrow(
+        deps.as_ref(),
+        &env,
+        ESCROW_SRC_CODE_ID.load(deps.storage)?,
+        immutables,
+        instantiate_msg,
+        escrow_funds(immutables, &safety_deposit_token)?,
+        format!("escrow-src-{}", &immutable_hash[..8]),
+    )?;
+    save_pending_escrow(deps.storage, EscrowKind::Src, immutable_hash, immutables, &escrow_address)?;
 
     Ok(Response::new()
-        .add_submessage(SubMsg::new(create_escrow_msg))
-        .add_attribute("method", "create_escrow_dst")
+        .add_submessage(create_escrow_msg)
+        .add_attribute("method", "create_escrow_src")
+        .add_attribute("escrow_address", escrow_address.to_string())
+        .add_attribute("order_hash", immutables.order_hash.clone())
         .add_attribute("hashlock", immutables.hashlock.clone())
-        .add_attribute("taker", immutables.taker.clone()))
+        .add_attribute("maker", immutables.maker.clone())
+        .add_attribute("taker", immutables.taker.clone())
+        .add_attribute("deployed_at", immutables.timelocks.deployed_at.to_string()))
+}
+
+/// Marks the fill at `index` of the order as having an escrow, rejecting a
+/// second escrow locked with the same secret. The hashes are keyed as the words
+/// they are hashed as, so `0x` prefixed or re-cased spellings are the same fill.
+fn use_fill(
+    storage: &mut dyn Storage,
+    immutables: &Immutables,
+    index: u64,
+) -> Result<(), ContractError> {
+    let order_hash = hex::encode(hex_to_word(&immutables.order_hash)?);
+    let hashlock = hex::encode(hex_to_word(&immutables.hashlock)?);
+    let key = (order_hash.as_str(), hashlock.as_str(), index);
+    if USED_FILLS.has(storage, key) {
+        return Err(ContractError::DuplicateEscrow {
+            order_hash,
+            hashlock,
+            index,
+        });
+    }
+    USED_FILLS.save(storage, key, &true)?;
+    Ok(())
+}
+
+/// Credits the sent funds to the sender's deposit
+pub fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(ContractError::MissingRequiredToken {
+            token: "any".to_string(),
+        });
+    }
+
+    for coin in info.funds.iter() {
+        DEPOSITS.update(
+            deps.storage,
+            (&info.sender, &coin.denom),
+            |balance| -> StdResult<_> {
+                Ok(balance.unwrap_or_default() + Uint256::from(coin.amount))
+            },
+        )?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "deposit")
+        .add_attribute("maker", info.sender.to_string()))
+}
+
+/// Debits `amount` of `denom` from the deposit of `owner`
+fn pull_deposit(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    denom: &str,
+    amount: Uint256,
+) -> Result<(), ContractError> {
+    let balance = DEPOSITS
+        .may_load(storage, (owner, denom))?
+        .unwrap_or_default();
+    let remaining = balance
+        .checked_sub(amount)
+        .map_err(|_| ContractError::InsufficientTokenAmount {
+            token: denom.to_string(),
+            expected: amount.to_string(),
+            actual: balance.to_string(),
+        })?;
+    DEPOSITS.save(storage, (owner, denom), &remaining)?;
+    Ok(())
+}
+
+/// Returns unused deposit funds to their owner
+pub fn withdraw_deposit(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    pull_deposit(deps.storage, &info.sender, &denom, amount)?;
+
+    let send_msg = Asset::from_uint256(AssetInfo::native(denom.clone()), amount)?
+        .transfer_msg(&info.sender)?;
+
+    Ok(Response::new()
+        .add_message(send_msg)
+        .add_attribute("method", "withdraw_deposit")
+        .add_attribute("maker", info.sender.to_string())
+        .add_attribute("denom", denom)
+        .add_attribute("amount", amount.to_string()))
+}
+
+/// Updates the escrow code ids, safety deposit denom, rescue delay, timelock limits, fee caps
+/// and fee treasury
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    escrow_dst_code_id: Option<u64>,
+    escrow_src_code_id: Option<u64>,
+    safety_deposit_token: Option<String>,
+    rescue_delay: Option<u32>,
+    min_timelock_windows: Option<TimelockWindows>,
+    fee_caps: Option<FeeCaps>,
+    fee_treasury: Option<String>,
+    clear_fee_treasury: bool,
+) -> Result<Response, ContractError> {
+    validate_owner(deps.as_ref(), &info.sender)?;
+
+    if clear_fee_treasury && fee_treasury.is_some() {
+        return Err(StdError::generic_err("Cannot both set and clear the fee treasury").into());
+    }
+
+    if let Some(code_id) = escrow_dst_code_id {
+        // fails for unknown code ids
+        get_code_hash(deps.querier, code_id)?;
+        ESCROW_DST_CODE_ID.save(deps.storage, &code_id)?;
+    }
+    if let Some(code_id) = escrow_src_code_id {
+        get_code_hash(deps.querier, code_id)?;
+        ESCROW_SRC_CODE_ID.save(deps.storage, &code_id)?;
+    }
+    if let Some(safety_deposit_token) = safety_deposit_token {
+        SAFETY_DEPOSIT_TOKEN.save(deps.storage, &safety_deposit_token)?;
+    }
+    if rescue_delay.is_some() || min_timelock_windows.is_some() {
+        let rescue_delay = match rescue_delay {
+            Some(rescue_delay) => rescue_delay,
+            None => RESCUE_DELAY.load(deps.storage)?,
+        };
+        let min_timelock_windows = match min_timelock_windows {
+            Some(min_timelock_windows) => min_timelock_windows,
+            None => TIMELOCK_WINDOWS.load(deps.storage)?,
+        };
+        validate_rescue_delay(rescue_delay)?;
+        min_timelock_windows.validate(rescue_delay)?;
+        RESCUE_DELAY.save(deps.storage, &rescue_delay)?;
+        TIMELOCK_WINDOWS.save(deps.storage, &min_timelock_windows)?;
+    }
+    if let Some(fee_caps) = fee_caps {
+        fee_caps.validate()?;
+        FEE_CAPS.save(deps.storage, &fee_caps)?;
+    }
+    if let Some(fee_treasury) = fee_treasury {
+        FEE_TREASURY.save(deps.storage, &deps.api.addr_validate(&fee_treasury)?)?;
+    }
+    if clear_fee_treasury {
+        FEE_TREASURY.remove(deps.storage);
+    }
+
+    Ok(Response::new().add_attribute("method", "update_config"))
+}
+
+/// Proposes a new owner, replacing any pending proposal
+pub fn transfer_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    validate_owner(deps.as_ref(), &info.sender)?;
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    PENDING_OWNER.save(deps.storage, &new_owner)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "transfer_ownership")
+        .add_attribute("pending_owner", new_owner.to_string()))
+}
+
+/// Completes an ownership transfer, called by the proposed owner
+pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if PENDING_OWNER.may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PENDING_OWNER.remove(deps.storage);
+    OWNER.save(deps.storage, &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_ownership")
+        .add_attribute("owner", info.sender.to_string()))
+}
+
+/// Migrates a batch of escrows created by this factory
+pub fn migrate_escrows(
+    deps: DepsMut,
+    info: MessageInfo,
+    immutables_hashes: Vec<String>,
+    new_code_id: u64,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    validate_owner(deps.as_ref(), &info.sender)?;
+
+    if immutables_hashes.len() > MAX_LIMIT as usize {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate more than {} escrows at once",
+            MAX_LIMIT
+        ))
+        .into());
+    }
+
+    let messages = immutables_hashes
+        .iter()
+        .map(|immutables_hash| {
+            let escrow = escrows().load(deps.storage, immutables_hash)?;
+            Ok(CosmosMsg::Wasm(WasmMsg::Migrate {
+                contract_addr: escrow.address.to_string(),
+                new_code_id,
+                msg: msg.clone(),
+            }))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "migrate_escrows")
+        .add_attribute("new_code_id", new_code_id.to_string())
+        .add_attribute("count", immutables_hashes.len().to_string()))
+}
+
+fn validate_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if OWNER.load(deps.storage)? != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+/// Builds a `WasmMsg::Instantiate2` salted with the immutables hash, so the
+/// escrow lands at the address predicted by `compute_escrow_address`.
+/// The escrow is registered when the instantiation replies.
+fn instantiate_escrow(
+    deps: Deps,
+    env: &Env,
+    code_id: u64,
+    immutables: &Immutables,
+    msg: Binary,
+    funds: Vec<Coin>,
+    label: String,
+) -> Result<(SubMsg, Addr), ContractError> {
+    let salt = compute_escrow_salt(immutables)?;
+    let escrow_address = compute_escrow_address(
+        deps.api,
+        immutables,
+        get_code_hash(deps.querier, code_id)?,
+        &deps.api.addr_canonicalize(env.contract.address.as_str())?,
+    )?;
+
+    let create_escrow_msg = CosmosMsg::Wasm(WasmMsg::Instantiate2 {
+        // the factory administers its escrows so it can migrate them
+        admin: Some(env.contract.address.to_string()),
+        code_id,
+        label,
+        msg,
+        funds,
+        salt,
+    });
+
+    Ok((
+        SubMsg::reply_on_success(create_escrow_msg, INSTANTIATE_ESCROW_REPLY_ID),
+        escrow_address,
+    ))
+}
+
+/// Rescue delay of a new escrow: the one in its immutables, within bounds, or the
+/// configured one. It must not end before `last_stage`, the escrow's last settlement stage.
+fn escrow_rescue_delay(
+    storage: &dyn Storage,
+    immutables: &Immutables,
+    last_stage: TimelockStage,
+) -> Result<u32, ContractError> {
+    let rescue_delay = match immutables.rescue_delay {
+        Some(rescue_delay) => {
+            validate_rescue_delay(rescue_delay)?;
+            rescue_delay
+        }
+        None => RESCUE_DELAY.load(storage)?,
+    };
+    immutables.timelocks.validate_rescue_delay(rescue_delay, last_stage)?;
+    Ok(rescue_delay)
+}
+
+/// Keeps the escrow being instantiated until its reply registers it
+fn save_pending_escrow(
+    storage: &mut dyn Storage,
+    kind: EscrowKind,
+    immutables_hash: String,
+    immutables: &Immutables,
+    escrow_address: &Addr,
+) -> Result<(), ContractError> {
+    PENDING_ESCROW.save(
+        storage,
+        &EscrowInfo {
+            immutables_hash,
+            kind,
+            address: escrow_address.clone(),
+            order_hash: immutables.order_hash.clone(),
+            hashlock: immutables.hashlock.clone(),
+            maker: immutables.maker.clone(),
+            taker: immutables.taker.clone(),
+            token: immutables.token.clone(),
+            amount: immutables.amount,
+            safety_deposit: immutables.safety_deposit,
+            deployed_at: immutables.timelocks.deployed_at,
+        },
+    )?;
+    Ok(())
+}
+
+#[entry_point]
+pub fn migrate(mut deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = check_migration(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    migrate_state(deps.branch(), &previous, msg)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", previous.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+/// State migrations, applied in order for every version newer than `previous`
+fn migrate_state(deps: DepsMut, previous: &Version, msg: MigrateMsg) -> Result<(), ContractError> {
+    // 0.2.0 added source escrows, ownership, timelock limits, the configurable
+    // rescue delay and fee caps
+    if *previous < Version::new(0, 2, 0) {
+        if !OWNER.exists(deps.storage) {
+            let owner = msg
+                .owner
+                .ok_or_else(|| StdError::generic_err("Migration requires an owner"))?;
+            OWNER.save(deps.storage, &deps.api.addr_validate(&owner)?)?;
+        }
+        if !ESCROW_SRC_CODE_ID.exists(deps.storage) {
+            let code_id = msg
+                .escrow_src_code_id
+                .ok_or_else(|| StdError::generic_err("Migration requires an escrow src code id"))?;
+            ESCROW_SRC_CODE_ID.save(deps.storage, &code_id)?;
+        }
+        if !TIMELOCK_WINDOWS.exists(deps.storage) {
+            TIMELOCK_WINDOWS.save(deps.storage, &TimelockWindows::default())?;
+        }
+        if !RESCUE_DELAY.exists(deps.storage) {
+            RESCUE_DELAY.save(deps.storage, &DEFAULT_RESCUE_DELAY)?;
+        }
+        if !FEE_CAPS.exists(deps.storage) {
+            FEE_CAPS.save(deps.storage, &FeeCaps::default())?;
+        }
+    }
+    Ok(())
+}
+
+#[entry_point]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_ESCROW_REPLY_ID => register_escrow(deps, msg),
+        id => Err(StdError::generic_err(format!("Unknown reply id: {}", id)).into()),
+    }
+}
+
+/// Checks that the escrow was instantiated at its predicted address, records
+/// it in the registry and announces it with an `escrow_created` event
+fn register_escrow(deps: DepsMut, msg: Reply) -> Result<Response, ContractError> {
+    let response = msg.result.into_result().map_err(StdError::generic_err)?;
+    let contract_address = response
+        .events
+        .iter()
+        .filter(|event| event.ty == "instantiate")
+        .flat_map(|event| event.attributes.iter())
+        .find(|attribute| attribute.key == "_contract_address")
+        .map(|attribute| attribute.value.clone())
+        .ok_or_else(|| StdError::generic_err("Missing escrow address in instantiate reply"))?;
+
+    let escrow = PENDING_ESCROW.load(deps.storage)?;
+    PENDING_ESCROW.remove(deps.storage);
+
+    if deps.api.addr_validate(&contract_address)? != escrow.address {
+        return Err(ContractError::EscrowAddressMismatch {
+            expected: escrow.address.to_string(),
+            actual: contract_address,
+        });
+    }
+
+    escrows().save(deps.storage, &escrow.immutables_hash, &escrow)?;
+
+    let kind = match escrow.kind {
+        EscrowKind::Src => "src",
+        EscrowKind::Dst => "dst",
+    };
+    let event = Event::new("escrow_created")
+        .add_attribute("kind", kind)
+        .add_attribute("escrow_address", escrow.address.to_string())
+        .add_attribute("immutables_hash", escrow.immutables_hash)
+        .add_attribute("order_hash", escrow.order_hash)
+        .add_attribute("hashlock", escrow.hashlock)
+        .add_attribute("maker", escrow.maker)
+        .add_attribute("taker", escrow.taker)
+        .add_attribute("token", escrow.token)
+        .add_attribute("amount", escrow.amount.to_string())
+        .add_attribute("safety_deposit", escrow.safety_deposit.to_string())
+        .add_attribute("deployed_at", escrow.deployed_at.to_string());
+
+    Ok(Response::new().add_event(event))
+}
+
+/// Funds sent to a source escrow: the maker's tokens plus the resolver's safety deposit
+fn escrow_funds(
+    immutables: &Immutables,
+    safety_deposit_token: &str,
+) -> Result<Vec<Coin>, ContractError> {
+    let mut funds: Vec<Coin> = vec![];
+    for (denom, amount) in [
+        (immutables.token.as_str(), immutables.amount),
+        (safety_deposit_token, immutables.safety_deposit),
+    ] {
+        if amount.is_zero() {
+            continue;
+        }
+        let amount = amount.try_into().map_err(|_| ContractError::UintConversionFailed {})?;
+        match funds.iter_mut().find(|coin| coin.denom == denom) {
+            Some(coin) => coin.amount += amount,
+            None => funds.push(Coin {
+                denom: denom.to_string(),
+                amount,
+            }),
+        }
+    }
+    funds.sort_by(|a, b| a.denom.cmp(&b.denom));
+    Ok(funds)
 }
 
 #[entry_point]
-pub fn query(_deps: Deps, _env: Env, _msg: QueryMsg) -> StdResult<Binary> {
-    return to_json_binary("no queries")
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::AddressOfEscrowDst {
+            immutables,
+            deployed_at,
+        } => to_json_binary(&query_address_of_escrow(
+            deps,
+            env,
+            ESCROW_DST_CODE_ID.load(deps.storage)?,
+            immutables,
+            deployed_at,
+        )?),
+        QueryMsg::AddressOfEscrowSrc {
+            immutables,
+            deployed_at,
+        } => to_json_binary(&query_address_of_escrow(
+            deps,
+            env,
+            ESCROW_SRC_CODE_ID.load(deps.storage)?,
+            immutables,
+            deployed_at,
+        )?),
+        QueryMsg::Escrows { start_after, limit } => {
+            to_json_binary(&query_escrows(deps, start_after, limit)?)
+        }
+        QueryMsg::EscrowByHashlock { hashlock } => {
+            to_json_binary(&query_escrow_by_hashlock(deps, hashlock)?)
+        }
+        QueryMsg::EscrowsByTaker {
+            taker,
+            start_after,
+            limit,
+        } => to_json_binary(&query_escrows_by_taker(deps, taker, start_after, limit)?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    Ok(ConfigResponse {
+        owner: OWNER.load(deps.storage)?,
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+        escrow_dst_code_id: ESCROW_DST_CODE_ID.load(deps.storage)?,
+        escrow_src_code_id: ESCROW_SRC_CODE_ID.load(deps.storage)?,
+        safety_deposit_token: SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
+        rescue_delay: RESCUE_DELAY.load(deps.storage)?,
+        min_timelock_windows: TIMELOCK_WINDOWS.load(deps.storage)?,
+        fee_caps: FEE_CAPS.load(deps.storage)?,
+        fee_treasury: FEE_TREASURY.may_load(deps.storage)?,
+    })
+}
+
+fn query_escrows(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<EscrowsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let escrows = escrows()
+        .range(deps.storage, start, None, StorageOrder::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, escrow)| escrow))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(EscrowsResponse { escrows })
+}
+
+fn query_escrow_by_hashlock(deps: Deps, hashlock: String) -> StdResult<Option<EscrowInfo>> {
+    escrows()
+        .idx
+        .hashlock
+        .prefix(hashlock)
+        .range(deps.storage, None, None, StorageOrder::Ascending)
+        .next()
+        .map(|item| item.map(|(_, escrow)| escrow))
+        .transpose()
+}
+
+fn query_escrows_by_taker(
+    deps: Deps,
+    taker: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<EscrowsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let escrows = escrows()
+        .idx
+        .taker
+        .prefix(taker)
+        .range(deps.storage, start, None, StorageOrder::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, escrow)| escrow))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(EscrowsResponse { escrows })
+}
+
+/// Predicts the address an escrow with `immutables` is deployed to by this factory
+fn query_address_of_escrow(
+    deps: Deps,
+    env: Env,
+    code_id: u64,
+    mut immutables: Immutables,
+    deployed_at: Option<u64>,
+) -> StdResult<Addr> {
+    if let Some(deployed_at) = deployed_at {
+        immutables.timelocks.deployed_at = deployed_at;
+    }
+
+    compute_escrow_address(
+        deps.api,
+        &immutables,
+        get_code_hash(deps.querier, code_id)?,
+        &deps.api.addr_canonicalize(env.contract.address.as_str())?,
+    )
+    .map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, mock_info, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        coins, Api, CodeInfoResponse, ContractResult, HexBinary, OwnedDeps, SubMsgResponse,
+        SubMsgResult, SystemError, SystemResult, WasmQuery,
+    };
+    use k256::ecdsa::signature::hazmat::PrehashSigner;
+    use k256::ecdsa::{Signature, SigningKey};
+    use ripemd::Ripemd160;
+    use sha2::{Digest, Sha256};
+    use shared::merkle::{hash_pair, merkle_leaf};
+    use shared::testing::{
+        attribute, mock_bech32_dependencies, mock_immutables, mock_timelocks, Bech32Api,
+    };
+    use shared::{adr36_sign_bytes, keccak256, Timelocks, Word};
+
+    type TestDeps = OwnedDeps<MockStorage, Bech32Api, MockQuerier>;
+
+    fn setup() -> (TestDeps, Env) {
+        let mut deps = mock_bech32_dependencies();
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::CodeInfo { code_id } => SystemResult::Ok(ContractResult::Ok(
+                to_json_binary(&CodeInfoResponse::new(
+                    *code_id,
+                    "creator".to_string(),
+                    HexBinary::from(&[*code_id as u8; 32]),
+                ))
+                .unwrap(),
+            )),
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "wasm".to_string(),
+            }),
+        });
+
+        let mut env = mock_env();
+        env.contract.address = deps.api.addr_make("factory");
+
+        let msg = InstantiateMsg {
+            escrow_dst_code_id: 1,
+            escrow_src_code_id: 2,
+            safety_deposit_token: "ustake".to_string(),
+            min_timelock_windows: None,
+            owner: None,
+            rescue_delay: None,
+            fee_caps: None,
+            fee_treasury: None,
+        };
+        let owner = deps.api.addr_make("owner");
+        instantiate(deps.as_mut(), env.clone(), mock_info(owner.as_str(), &[]), msg).unwrap();
+
+        (deps, env)
+    }
+
+    fn maker_key() -> SigningKey {
+        SigningKey::from_bytes(&[7u8; 32].into()).unwrap()
+    }
+
+    fn pubkey(key: &SigningKey) -> Binary {
+        Binary::from(key.verifying_key().to_encoded_point(true).as_bytes())
+    }
+
+    /// Address of the account holding `key`
+    fn key_address(api: &Bech32Api, key: &SigningKey) -> String {
+        let hash = Ripemd160::digest(Sha256::digest(pubkey(key).as_slice()));
+        api.addr_humanize(&hash.to_vec().into()).unwrap().to_string()
+    }
+
+    fn src_order(api: &Bech32Api, env: &Env, key: &SigningKey) -> Order {
+        Order {
+            salt: Uint256::from(1u8),
+            maker: key_address(api, key),
+            receiver: "0x1111111111111111111111111111111111111111".to_string(),
+            maker_asset: "uatom".to_string(),
+            taker_asset: "0x2222222222222222222222222222222222222222".to_string(),
+            making_amount: Uint256::from(900u128),
+            taking_amount: Uint256::from(900u128),
+            hashlock: keccak256(b"secret"),
+            allow_multiple_fills: false,
+            src_safety_deposit: Uint256::from(100u128),
+            dst_safety_deposit: Uint256::from(100u128),
+            timelocks: mock_timelocks(),
+            dst_chain_id: "1".to_string(),
+            src_chain_id: env.block.chain_id.clone(),
+            factory: env.contract.address.to_string(),
+            expiration: 0,
+            rescue_delay: None,
+        }
+    }
+
+    /// ADR-36 signature of the order hash by `key`
+    fn sign_order(order: &Order, key: &SigningKey) -> Binary {
+        let order_hash = order.compute_order_hash().unwrap();
+        let sign_bytes = adr36_sign_bytes(&order.maker, order_hash.as_bytes());
+        let signature: Signature = key.sign_prehash(&Sha256::digest(sign_bytes)).unwrap();
+        Binary::from(signature.to_bytes().as_slice())
+    }
+
+    fn src_immutables(api: &Bech32Api, order: &Order, hashlock: &str, amount: u128) -> Immutables {
+        Immutables {
+            order_hash: order.compute_order_hash().unwrap(),
+            hashlock: hashlock.to_string(),
+            maker: order.maker.clone(),
+            taker: api.addr_make("resolver").to_string(),
+            token: order.maker_asset.clone(),
+            amount: Uint256::from(amount),
+            safety_deposit: order.src_safety_deposit,
+            timelocks: order.timelocks.clone(),
+            parameters: vec![],
+            rescue_delay: order.rescue_delay,
+        }
+    }
+
+    /// Order split into 3 parts locked by 4 secrets, with the secret hashes
+    /// and the leaves of their Merkle tree
+    fn multiple_fills_order(api: &Bech32Api, env: &Env, key: &SigningKey) -> (Order, Vec<String>, Vec<Word>) {
+        let secret_hashes: Vec<String> = (0u8..4).map(|i| keccak256(&[i])).collect();
+        let leaves: Vec<Word> = secret_hashes
+            .iter()
+            .enumerate()
+            .map(|(index, secret_hash)| merkle_leaf(index as u64, secret_hash).unwrap())
+            .collect();
+        let mut hashlock_info = hash_pair(hash_pair(leaves[0], leaves[1]), hash_pair(leaves[2], leaves[3]));
+        hashlock_info[0] = 0;
+        hashlock_info[1] = 3;
+        let order = Order {
+            hashlock: hex::encode(hashlock_info),
+            allow_multiple_fills: true,
+            ..src_order(api, env, key)
+        };
+        (order, secret_hashes, leaves)
+    }
+
+    fn create_escrow_src_msg(
+        immutables: Immutables,
+        order: &Order,
+        signature: Binary,
+        key: &SigningKey,
+        merkle_fill: Option<MerkleFill>,
+    ) -> ExecuteMsg {
+        ExecuteMsg::CreateEscrowSrc {
+            immutables: Box::new(immutables),
+            order: Box::new(order.clone()),
+            signature,
+            maker_pubkey: pubkey(key),
+            merkle_fill,
+        }
+    }
+
+    fn deposit_maker_funds(deps: &mut TestDeps, env: &Env, maker: &str, amount: u128) {
+        execute(deps.as_mut(), env.clone(), mock_info(maker, &coins(amount, "uatom")), ExecuteMsg::Deposit {}).unwrap();
+    }
+
+    fn create_escrow_dst_msg(immutables: Immutables, env: &Env) -> ExecuteMsg {
+        ExecuteMsg::CreateEscrowDst {
+            immutables: Box::new(immutables),
+            src_cancellation_timestamp: env.block.time.seconds() + 100,
+            packed_timelocks: None,
+        }
+    }
+
+    fn instantiate_reply(contract_address: &str) -> Reply {
+        Reply {
+            id: INSTANTIATE_ESCROW_REPLY_ID,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![Event::new("instantiate").add_attribute("_contract_address", contract_address)],
+                data: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_create_escrow_dst_and_register_on_reply() {
+        let (mut deps, env) = setup();
+        let immutables = mock_immutables("22".repeat(32));
+        let funds = [coins(1_000, "uatom"), coins(100, "ustake")].concat();
+        let info = mock_info(&immutables.taker, &funds);
+
+        let response = execute(deps.as_mut(), env.clone(), info, create_escrow_dst_msg(immutables, &env)).unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(response.messages[0].id, INSTANTIATE_ESCROW_REPLY_ID);
+        let escrow_address = attribute(&response, "escrow_address");
+
+        let response = reply(deps.as_mut(), env.clone(), instantiate_reply(&escrow_address)).unwrap();
+        assert_eq!(response.events[0].ty, "escrow_created");
+
+        let escrows = query_escrows(deps.as_ref(), None, None).unwrap().escrows;
+        assert_eq!(escrows.len(), 1);
+        assert_eq!(escrows[0].address.as_str(), escrow_address);
+        assert!(!PENDING_ESCROW.exists(&deps.storage));
+    }
+
+    #[test]
+    fn test_create_escrow_src_with_signed_order() {
+        let (mut deps, env) = setup();
+        let key = maker_key();
+        let order = src_order(&deps.api, &env, &key);
+        let signature = sign_order(&order, &key);
+        let immutables = src_immutables(&deps.api, &order, &order.hashlock, 900);
+        let maker = deps.api.addr_validate(&order.maker).unwrap();
+        deposit_maker_funds(&mut deps, &env, maker.as_str(), 1_000);
+
+        let info = mock_info(&immutables.taker, &coins(100, "ustake"));
+        let msg = create_escrow_src_msg(immutables.clone(), &order, signature, &key, None);
+        let response = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(attribute(&response, "deployed_at"), env.block.time.seconds().to_string());
+
+        let remaining = ORDER_REMAINING.load(&deps.storage, &immutables.order_hash).unwrap();
+        assert_eq!(remaining, Uint256::zero());
+        let deposit = DEPOSITS.load(&deps.storage, (&maker, "uatom")).unwrap();
+        assert_eq!(deposit, Uint256::from(100u128));
+    }
+
+    #[test]
+    fn test_create_escrow_src_requires_exact_safety_deposit() {
+        let (mut deps, env) = setup();
+        let key = maker_key();
+        let order = src_order(&deps.api, &env, &key);
+        let signature = sign_order(&order, &key);
+        deposit_maker_funds(&mut deps, &env, &order.maker, 900);
+        let immutables = src_immutables(&deps.api, &order, &order.hashlock, 900);
+
+        for funds in [coins(150, "ustake"), [coins(100, "ustake"), coins(1, "uatom")].concat()] {
+            let info = mock_info(&immutables.taker, &funds);
+            let msg = create_escrow_src_msg(immutables.clone(), &order, signature.clone(), &key, None);
+            let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+            assert!(matches!(err, ContractError::UnexpectedTokenAmount { .. }));
+        }
+    }
+
+    #[test]
+    fn test_rescue_delay_must_cover_settlement() {
+        let (mut deps, env) = setup();
+        let mut immutables = mock_immutables("22".repeat(32));
+        immutables.timelocks.dst_cancellation = 7200;
+        immutables.rescue_delay = Some(3600);
+        let funds = [coins(1_000, "uatom"), coins(100, "ustake")].concat();
+        let info = mock_info(&immutables.taker, &funds);
+        let msg = ExecuteMsg::CreateEscrowDst {
+            immutables: Box::new(immutables),
+            src_cancellation_timestamp: env.block.time.seconds() + 10_000,
+            packed_timelocks: None,
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::RescueDelayTooShort { stage, .. } if stage == "DstCancellation"));
+
+        let key = maker_key();
+        let order = Order {
+            timelocks: Timelocks {
+                src_public_cancellation: 7200,
+                ..mock_timelocks()
+            },
+            rescue_delay: Some(3600),
+            ..src_order(&deps.api, &env, &key)
+        };
+        deposit_maker_funds(&mut deps, &env, &order.maker, 900);
+        let immutables = src_immutables(&deps.api, &order, &order.hashlock, 900);
+        let info = mock_info(&immutables.taker, &coins(100, "ustake"));
+        let msg = create_escrow_src_msg(immutables, &order, sign_order(&order, &key), &key, None);
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::RescueDelayTooShort { stage, .. } if stage == "SrcPublicCancellation"));
+    }
+
+    #[test]
+    fn test_create_escrow_src_rejects_bad_signatures() {
+        let (mut deps, env) = setup();
+        let key = maker_key();
+        let order = src_order(&deps.api, &env, &key);
+        let signature = sign_order(&order, &key);
+        deposit_maker_funds(&mut deps, &env, &order.maker, 1_000);
+
+        // the order is changed after signing
+        let tampered = Order {
+            taking_amount: Uint256::from(1u8),
+            ..order.clone()
+        };
+        let immutables = src_immutables(&deps.api, &tampered, &tampered.hashlock, 900);
+        let info = mock_info(&immutables.taker, &coins(100, "ustake"));
+        let msg = create_escrow_src_msg(immutables, &tampered, signature.clone(), &key, None);
+        let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+
+        // a valid signature by a key that does not derive the maker's address
+        let other_key = SigningKey::from_bytes(&[8u8; 32].into()).unwrap();
+        let immutables = src_immutables(&deps.api, &order, &order.hashlock, 900);
+        let msg = create_escrow_src_msg(immutables, &order, sign_order(&order, &other_key), &other_key, None);
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSignature {}));
+    }
+
+    #[test]
+    fn test_create_escrow_src_rejects_replays() {
+        let (mut deps, env) = setup();
+        let key = maker_key();
+        let maker = key_address(&deps.api, &key);
+        deposit_maker_funds(&mut deps, &env, &maker, 2_000);
+
+        // signed for another chain or another factory
+        let other_chain = Order {
+            src_chain_id: "other-chain".to_string(),
+            ..src_order(&deps.api, &env, &key)
+        };
+        let other_factory = Order {
+            factory: deps.api.addr_make("other_factory").to_string(),
+            ..src_order(&deps.api, &env, &key)
+        };
+        for order in [other_chain, other_factory] {
+            let immutables = src_immutables(&deps.api, &order, &order.hashlock, 900);
+            let info = mock_info(&immutables.taker, &coins(100, "ustake"));
+            let msg = create_escrow_src_msg(immutables, &order, sign_order(&order, &key), &key, None);
+            let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+            assert!(matches!(err, ContractError::InvalidOrderDomain {}));
+        }
+
+        // the filled order is submitted again
+        let order = src_order(&deps.api, &env, &key);
+        let immutables = src_immutables(&deps.api, &order, &order.hashlock, 900);
+        let info = mock_info(&immutables.taker, &coins(100, "ustake"));
+        let msg = create_escrow_src_msg(immutables, &order, sign_order(&order, &key), &key, None);
+        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPartialFill {}));
+    }
+
+    #[test]
+    fn test_create_escrow_src_multiple_fills() {
+        let (mut deps, env) = setup();
+        let key = maker_key();
+
+        let (order, secret_hashes, leaves) = multiple_fills_order(&deps.api, &env, &key);
+        let (left, right) = (hash_pair(leaves[0], leaves[1]), hash_pair(leaves[2], leaves[3]));
+        let signature = sign_order(&order, &key);
+        deposit_maker_funds(&mut deps, &env, &order.maker, 900);
+
+        let fill = |index: u64, sibling, uncle| MerkleFill {
+            hashlock_info: order.hashlock.clone(),
+            index,
+            proof: vec![hex::encode(sibling), hex::encode(uncle)],
+        };
+        let fills = [
+            (0, fill(0, leaves[1], right), 600u128),
+            (1, fill(1, leaves[0], right), 300),
+            (3, fill(3, leaves[2], left), 0),
+        ];
+        for (index, merkle_fill, remaining) in fills {
+            let immutables = src_immutables(&deps.api, &order, &secret_hashes[index], 300);
+            let info = mock_info(&immutables.taker, &coins(100, "ustake"));
+            let msg = create_escrow_src_msg(immutables.clone(), &order, signature.clone(), &key, Some(merkle_fill));
+            execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+            let order_remaining = ORDER_REMAINING.load(&deps.storage, &immutables.order_hash).unwrap();
+            assert_eq!(order_remaining, Uint256::from(remaining));
+        }
+    }
+
+    #[test]
+    fn test_create_escrow_src_rejects_wrong_fill_index() {
+        let (mut deps, env) = setup();
+        let key = maker_key();
+        let (order, secret_hashes, leaves) = multiple_fills_order(&deps.api, &env, &key);
+        let right = hash_pair(leaves[2], leaves[3]);
+        deposit_maker_funds(&mut deps, &env, &order.maker, 900);
+
+        // a first fill of a third must use the first secret, not the second
+        let immutables = src_immutables(&deps.api, &order, &secret_hashes[1], 300);
+        let merkle_fill = MerkleFill {
+            hashlock_info: order.hashlock.clone(),
+            index: 1,
+            proof: vec![hex::encode(leaves[0]), hex::encode(right)],
+        };
+        let info = mock_info(&immutables.taker, &coins(100, "ustake"));
+        let msg = create_escrow_src_msg(immutables, &order, sign_order(&order, &key), &key, Some(merkle_fill));
+        let err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidPartialFill {}));
+    }
+
+    #[test]
+    fn test_duplicate_escrow_dst_with_respelled_hashes() {
+        let (mut deps, mut env) = setup();
+        let immutables = mock_immutables("22".repeat(32));
+        let funds = [coins(1_000, "uatom"), coins(100, "ustake")].concat();
+        let info = mock_info(&immutables.taker, &funds);
+        execute(deps.as_mut(), env.clone(), info.clone(), create_escrow_dst_msg(immutables.clone(), &env)).unwrap();
+
+        // a later block changes the immutables hash, but not the fill
+        env.block.time = env.block.time.plus_seconds(5);
+        let respelled = Immutables {
+            order_hash: format!("0x{}", immutables.order_hash.to_uppercase()),
+            hashlock: immutables.hashlock.to_uppercase(),
+            ..immutables
+        };
+        let err = execute(deps.as_mut(), env.clone(), info, create_escrow_dst_msg(respelled, &env)).unwrap_err();
+        assert!(matches!(err, ContractError::DuplicateEscrow { index: 0, .. }));
+    }
+
+    #[test]
+    fn test_cw20_escrow_dst_returns_excess() {
+        let (mut deps, env) = setup();
+        let token = deps.api.addr_make("token");
+        let immutables = Immutables {
+            token: token.to_string(),
+            ..mock_immutables("22".repeat(32))
+        };
+        let resolver = immutables.taker.clone();
+        execute(deps.as_mut(), env.clone(), mock_info(&resolver, &coins(100, "ustake")), ExecuteMsg::Deposit {}).unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: resolver.clone(),
+            amount: 1_500u128.into(),
+            msg: to_json_binary(&ReceiveMsg::CreateEscrowDst {
+                immutables,
+                src_cancellation_timestamp: env.block.time.seconds() + 100,
+                packed_timelocks: None,
+            })
+            .unwrap(),
+        });
+        let response = execute(deps.as_mut(), env.clone(), mock_info(token.as_str(), &[]), msg).unwrap();
+        let escrow_address = attribute(&response, "escrow_address");
+
+        let transfers: Vec<cw20::Cw20ExecuteMsg> = response.messages[1..]
+            .iter()
+            .map(|message| match &message.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => from_json(msg).unwrap(),
+                other => panic!("unexpected message {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            transfers,
+            vec![
+                cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: escrow_address,
+                    amount: 1_000u128.into(),
+                },
+                cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: resolver,
+                    amount: 500u128.into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reply_rejects_unexpected_address() {
+        let (mut deps, env) = setup();
+        let immutables = mock_immutables("22".repeat(32));
+        let funds = [coins(1_000, "uatom"), coins(100, "ustake")].concat();
+        let info = mock_info(&immutables.taker, &funds);
+        execute(deps.as_mut(), env.clone(), info, create_escrow_dst_msg(immutables, &env)).unwrap();
+
+        let other = deps.api.addr_make("other");
+        let err = reply(deps.as_mut(), env, instantiate_reply(other.as_str())).unwrap_err();
+        assert!(matches!(err, ContractError::EscrowAddressMismatch { .. }));
+    }
+
+    #[test]
+    fn test_update_config_rejects_invalid_fee_caps() {
+        let (mut deps, env) = setup();
+        let owner = deps.api.addr_make("owner");
+
+        let msg = ExecuteMsg::UpdateConfig {
+            escrow_dst_code_id: None,
+            escrow_src_code_id: None,
+            safety_deposit_token: None,
+            rescue_delay: None,
+            min_timelock_windows: None,
+            fee_caps: Some(FeeCaps {
+                max_fee_legs: 11,
+                ..FeeCaps::default()
+            }),
+            fee_treasury: None,
+            clear_fee_treasury: false,
+        };
+        let err = execute(deps.as_mut(), env, mock_info(owner.as_str(), &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidFeeCap { value: 11, max: 10, .. }));
+    }
+
+    #[test]
+    fn test_update_config_validates_timelock_windows() {
+        let (mut deps, env) = setup();
+        let owner = deps.api.addr_make("owner");
+        let update = |rescue_delay, min_timelock_windows| ExecuteMsg::UpdateConfig {
+            escrow_dst_code_id: None,
+            escrow_src_code_id: None,
+            safety_deposit_token: None,
+            rescue_delay,
+            min_timelock_windows,
+            fee_caps: None,
+            fee_treasury: None,
+            clear_fee_treasury: false,
+        };
+
+        let zero_window = TimelockWindows {
+            min_withdrawal: 0,
+            ..TimelockWindows::default()
+        };
+        let err = execute(deps.as_mut(), env.clone(), mock_info(owner.as_str(), &[]), update(None, Some(zero_window))).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTimelockWindow { value: 0, .. }));
+
+        let windows = TimelockWindows {
+            min_withdrawal: 7200,
+            ..TimelockWindows::default()
+        };
+        execute(deps.as_mut(), env.clone(), mock_info(owner.as_str(), &[]), update(None, Some(windows))).unwrap();
+        // a shorter rescue delay must still cover the configured windows
+        let err = execute(deps.as_mut(), env, mock_info(owner.as_str(), &[]), update(Some(3600), None)).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidTimelockWindow { value: 7200, max: 3600, .. }));
+    }
+
+    #[test]
+    fn test_update_config_clears_fee_treasury() {
+        let (mut deps, env) = setup();
+        let owner = deps.api.addr_make("owner");
+        let treasury = deps.api.addr_make("treasury");
+        let update = |fee_treasury, clear_fee_treasury| ExecuteMsg::UpdateConfig {
+            escrow_dst_code_id: None,
+            escrow_src_code_id: None,
+            safety_deposit_token: None,
+            rescue_delay: None,
+            min_timelock_windows: None,
+            fee_caps: None,
+            fee_treasury,
+            clear_fee_treasury,
+        };
+
+        execute(deps.as_mut(), env.clone(), mock_info(owner.as_str(), &[]), update(Some(treasury.to_string()), false)).unwrap();
+        assert_eq!(FEE_TREASURY.load(&deps.storage).unwrap(), treasury);
+
+        execute(deps.as_mut(), env.clone(), mock_info(owner.as_str(), &[]), update(Some(treasury.to_string()), true)).unwrap_err();
+        execute(deps.as_mut(), env, mock_info(owner.as_str(), &[]), update(None, true)).unwrap();
+        assert!(!FEE_TREASURY.exists(&deps.storage));
+    }
 }
@@ -1,5 +1,52 @@
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, Uint256};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use interfaces::escrow_factory::EscrowInfo;
+use shared::{FeeCaps, TimelockWindows};
+pub const OWNER: Item<Addr> = Item::new("owner");
+pub const PENDING_OWNER: Item<Addr> = Item::new("pending_owner");
 pub const ESCROW_DST_CODE_ID: Item<u64> = Item::new("escrow_dst_code_id");
+pub const ESCROW_SRC_CODE_ID: Item<u64> = Item::new("escrow_src_code_id");
 pub const SAFETY_DEPOSIT_TOKEN: Item<String> = Item::new("safety_deposit_token");
+pub const TIMELOCK_WINDOWS: Item<TimelockWindows> = Item::new("timelock_windows");
+pub const RESCUE_DELAY: Item<u32> = Item::new("rescue_delay");
+pub const FEE_CAPS: Item<FeeCaps> = Item::new("fee_caps");
+/// Only allowed protocol fee recipient of destination escrows, when set
+pub const FEE_TREASURY: Item<Addr> = Item::new("fee_treasury");
+/// Making amount left to fill for source orders, keyed by order hash
+pub const ORDER_REMAINING: Map<&str, Uint256> = Map::new("order_remaining");
+/// Funds deposited with the factory, keyed by (owner, denom): maker funds for
+/// source orders and resolver safety deposits for CW20 destination escrows
+pub const DEPOSITS: Map<(&Addr, &str), Uint256> = Map::new("deposits");
+/// Fills that already have an escrow, keyed by (order hash, hashlock, fill index)
+/// as lower-case hex words. The fill index is the Merkle leaf index of partial
+/// source fills and 0 otherwise.
+pub const USED_FILLS: Map<(&str, &str, u64), bool> = Map::new("used_fills");
+/// Escrow whose instantiation is awaiting its reply
+pub const PENDING_ESCROW: Item<EscrowInfo> = Item::new("pending_escrow");
 
-pub const RESCUE_DELAY: u32 = 86400;
\ No newline at end of file
+pub struct EscrowIndexes<'a> {
+    pub order_hash: MultiIndex<'a, String, EscrowInfo, String>,
+    pub hashlock: MultiIndex<'a, String, EscrowInfo, String>,
+    pub maker: MultiIndex<'a, String, EscrowInfo, String>,
+    pub taker: MultiIndex<'a, String, EscrowInfo, String>,
+}
+
+impl<'a> IndexList<EscrowInfo> for EscrowIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<EscrowInfo>> + '_> {
+        let v: Vec<&dyn Index<EscrowInfo>> = vec![&self.order_hash, &self.hashlock, &self.maker, &self.taker];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Registry of created escrows, keyed by immutables hash
+pub fn escrows<'a>() -> IndexedMap<'a, &'a str, EscrowInfo, EscrowIndexes<'a>> {
+    let indexes = EscrowIndexes {
+        order_hash: MultiIndex::new(|_pk, e| e.order_hash.clone(), "escrows", "escrows__order_hash"),
+        hashlock: MultiIndex::new(|_pk, e| e.hashlock.clone(), "escrows", "escrows__hashlock"),
+        maker: MultiIndex::new(|_pk, e| e.maker.clone(), "escrows", "escrows__maker"),
+        taker: MultiIndex::new(|_pk, e| e.taker.clone(), "escrows", "escrows__taker"),
+    };
+    IndexedMap::new("escrows", indexes)
+}
+
+pub const DEFAULT_RESCUE_DELAY: u32 = 86400;
@@ -0,0 +1,558 @@
+use cosmwasm_std::{to_json_binary, HexBinary, Uint256};
+use cosmwasm_std::{
+    entry_point, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+};
+use cw2::set_contract_version;
+
+use shared::asset::{Asset, AssetInfo};
+use shared::types::Immutables;
+use shared::types::{EscrowStatus, TimelockStage};
+use shared::error::ContractError;
+use interfaces::escrow_src::{BalanceResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::state::{FACTORY, IMMUTABLES, IMMUTABLE_HASH, SAFETY_DEPOSIT_TOKEN, STATUS};
+use crate::RESCUE_DELAY;
+
+use shared::escrow::{resolve_immutables, update_status};
+use shared::migrate::check_migration;
+use shared::utils::{get_code_hash, validate_secret};
+
+const CONTRACT_NAME: &str = "crates.io:escrow-src";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    FACTORY.save(deps.storage, &info.sender)?;
+
+    SAFETY_DEPOSIT_TOKEN.save(deps.storage, &msg.safety_deposit_denom)?;
+
+    IMMUTABLE_HASH.save(deps.storage, &msg.immutable_hash)?;
+
+    if let Some(immutables) = msg.immutables {
+        if immutables.compute_immutables_hash()? != msg.immutable_hash {
+            return Err(ContractError::InvalidImmutables {});
+        }
+        IMMUTABLES.save(deps.storage, &immutables)?;
+    }
+
+    RESCUE_DELAY.save(deps.storage, &msg.rescue_delay)?;
+
+    STATUS.save(deps.storage, &EscrowStatus::Active)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate"))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Withdraw { secret, immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            withdraw(deps, env, info, secret, immutables)
+        }
+        ExecuteMsg::WithdrawTo { secret, target, immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            withdraw_to(deps, env, info, secret, target, immutables)
+        }
+        ExecuteMsg::PublicWithdraw { secret, immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            public_withdraw(deps, env, info, secret, immutables)
+        }
+        ExecuteMsg::Cancel { immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            cancel(deps, env, info, immutables)
+        }
+        ExecuteMsg::PublicCancel { immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            public_cancel(deps, env, info, immutables)
+        }
+        ExecuteMsg::RescueFunds { token, amount, immutables } => {
+            let immutables = resolve_immutables(deps.storage, immutables)?;
+            rescue_funds(deps, env, info, token, amount, immutables)
+        }
+    }
+}
+
+/// Private withdrawal by the taker, paying the escrowed tokens to the taker
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    secret: Binary,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    let target = info.sender.to_string();
+    withdraw_to(deps, env, info, secret, target, immutables)
+}
+
+/// Private withdrawal by the taker, paying the escrowed tokens to `target`
+pub fn withdraw_to(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    secret: Binary,
+    target: String,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    if info.sender != immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::SrcWithdrawal) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+    if env.block.time.seconds() >= immutables.timelocks.get_timelock(TimelockStage::SrcCancellation) {
+        return Err(ContractError::TimelockHasCrossed {});
+    }
+
+    let target = deps.api.addr_validate(&target)?;
+
+    _withdraw_to(deps, &info, secret, target.to_string(), &immutables)
+}
+
+/// Public withdrawal by anyone, paying the escrowed tokens to the taker
+pub fn public_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    secret: Binary,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::SrcPublicWithdrawal) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+
+    // Check if cancellation timelock has not been reached yet
+    if env.block.time.seconds() >= immutables.timelocks.get_timelock(TimelockStage::SrcCancellation) {
+        return Err(ContractError::TimelockHasCrossed {});
+    }
+
+    let target = immutables.taker.clone();
+    _withdraw_to(deps, &info, secret, target, &immutables)
+}
+
+/// Private cancellation by the taker, returning the escrowed tokens to the maker
+pub fn cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    if info.sender != immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::SrcCancellation) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+
+    _cancel(deps, &info, &immutables, "cancel")
+}
+
+/// Public cancellation by anyone, returning the escrowed tokens to the maker
+pub fn public_cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::SrcPublicCancellation) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+
+    _cancel(deps, &info, &immutables, "public_cancel")
+}
+
+pub fn rescue_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    amount: Uint256,
+    immutables: Immutables,
+) -> Result<Response, ContractError> {
+    if info.sender != immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::RescueDelay(RESCUE_DELAY.load(deps.storage)?)) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+
+    update_status(deps.storage, EscrowStatus::Rescued)?;
+
+    let mut messages = vec![];
+
+    let token = AssetInfo::from_token(deps.api, &token);
+    messages.push(Asset::from_uint256(token, amount)?.transfer_msg(&immutables.taker)?);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "rescue_funds"))
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let previous = check_migration(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", previous.to_string())
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::QueryFactoryAddress {} => to_json_binary(&FACTORY.load(deps.storage)?),
+        QueryMsg::QueryEscrowSrcCodeHash {} => to_json_binary(&query_code_hash(deps, env)?),
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Balance {} => to_json_binary(&query_balance(deps, env)?),
+        QueryMsg::Status {} => to_json_binary(&STATUS.load(deps.storage)?),
+    }
+}
+
+fn query_code_hash(deps: Deps, env: Env) -> StdResult<HexBinary> {
+    let contract_info = deps.querier.query_wasm_contract_info(env.contract.address)?;
+    get_code_hash(deps.querier, contract_info.code_id)
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    Ok(ConfigResponse {
+        safety_deposit_denom: SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
+        rescue_delay: RESCUE_DELAY.load(deps.storage)?,
+        immutable_hash: IMMUTABLE_HASH.load(deps.storage)?,
+    })
+}
+
+fn query_balance(deps: Deps, env: Env) -> StdResult<BalanceResponse> {
+    let token = match IMMUTABLES.may_load(deps.storage)? {
+        Some(immutables) => {
+            let info = AssetInfo::from_token(deps.api, &immutables.token);
+            let amount = info.query_balance(&deps.querier, &env.contract.address)?;
+            Some(Asset::new(info, amount))
+        }
+        None => None,
+    };
+    Ok(BalanceResponse {
+        balances: deps.querier.query_all_balances(&env.contract.address)?,
+        token,
+    })
+}
+
+/// Internal withdraw function that releases the maker's tokens to `target`
+fn _withdraw_to(
+    deps: DepsMut,
+    info: &MessageInfo,
+    secret: Binary,
+    target: String,
+    immutables: &Immutables,
+) -> Result<Response, ContractError> {
+    if !validate_secret(&secret, &immutables.hashlock) {
+        return Err(ContractError::InvalidSecret {});
+    }
+
+    update_status(deps.storage, EscrowStatus::Withdrawn)?;
+
+    let mut messages = vec![];
+
+    if immutables.amount > Uint256::zero() {
+        let token = AssetInfo::from_token(deps.api, &immutables.token);
+        messages.push(Asset::from_uint256(token, immutables.amount)?.transfer_msg(&target)?);
+    }
+
+    // Transfer safety deposit to caller (msg.sender)
+    if immutables.safety_deposit > Uint256::zero() {
+        let safety_deposit = AssetInfo::native(SAFETY_DEPOSIT_TOKEN.load(deps.storage)?);
+        messages.push(Asset::from_uint256(safety_deposit, immutables.safety_deposit)?.transfer_msg(&info.sender)?);
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "_withdraw_to")
+        .add_attribute("secret", secret.to_string())
+        .add_attribute("target", target)
+        .add_attribute("amount", immutables.amount.to_string())
+        .add_attribute("safety_deposit", immutables.safety_deposit.to_string()))
+}
+
+/// Internal cancel function that returns the escrowed tokens to the maker
+fn _cancel(
+    deps: DepsMut,
+    info: &MessageInfo,
+    immutables: &Immutables,
+    method: &str,
+) -> Result<Response, ContractError> {
+    update_status(deps.storage, EscrowStatus::Cancelled)?;
+
+    let mut messages = vec![];
+
+    if immutables.amount > Uint256::zero() {
+        let token = AssetInfo::from_token(deps.api, &immutables.token);
+        messages.push(Asset::from_uint256(token, immutables.amount)?.transfer_msg(&immutables.maker)?);
+    }
+
+    // Transfer safety deposit to caller (msg.sender)
+    if immutables.safety_deposit > Uint256::zero() {
+        let safety_deposit = AssetInfo::native(SAFETY_DEPOSIT_TOKEN.load(deps.storage)?);
+        messages.push(Asset::from_uint256(safety_deposit, immutables.safety_deposit)?.transfer_msg(&info.sender)?);
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", method)
+        .add_attribute("maker", immutables.maker.clone())
+        .add_attribute("amount", immutables.amount.to_string())
+        .add_attribute("safety_deposit", immutables.safety_deposit.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_env, mock_info, MockQuerier, MockStorage};
+    use cosmwasm_std::{coins, from_json, BankMsg, CosmosMsg, OwnedDeps};
+    use shared::testing::{mock_bech32_dependencies, Bech32Api};
+    use shared::types::Timelocks;
+    use shared::utils::keccak256;
+
+    type TestDeps = OwnedDeps<MockStorage, Bech32Api, MockQuerier>;
+
+    const SECRET: &[u8] = b"secret";
+    const RESCUE_DELAY_SECONDS: u32 = 3600;
+
+    fn src_immutables(api: &Bech32Api, env: &Env) -> Immutables {
+        Immutables {
+            order_hash: "11".repeat(32),
+            hashlock: keccak256(SECRET),
+            maker: api.addr_make("maker").to_string(),
+            taker: api.addr_make("taker").to_string(),
+            token: "uatom".to_string(),
+            amount: Uint256::from(1_000u128),
+            safety_deposit: Uint256::from(100u128),
+            timelocks: Timelocks {
+                deployed_at: env.block.time.seconds(),
+                src_withdrawal: 10,
+                src_public_withdrawal: 20,
+                src_cancellation: 30,
+                src_public_cancellation: 40,
+                dst_withdrawal: 10,
+                dst_public_withdrawal: 20,
+                dst_cancellation: 30,
+            },
+            parameters: vec![],
+            rescue_delay: None,
+        }
+    }
+
+    fn instantiate_escrow(deps: &mut TestDeps, env: &Env, immutables: &Immutables) {
+        let msg = InstantiateMsg {
+            safety_deposit_denom: "uatom".to_string(),
+            rescue_delay: RESCUE_DELAY_SECONDS,
+            immutable_hash: immutables.compute_immutables_hash().unwrap(),
+            immutables: Some(immutables.clone()),
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info("factory", &[]), msg).unwrap();
+    }
+
+    fn setup() -> (TestDeps, Env, Immutables) {
+        let mut deps = mock_bech32_dependencies();
+        let env = mock_env();
+        let immutables = src_immutables(&deps.api, &env);
+        instantiate_escrow(&mut deps, &env, &immutables);
+        (deps, env, immutables)
+    }
+
+    fn after(env: &Env, seconds: u64) -> Env {
+        let mut env = env.clone();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    }
+
+    fn withdraw_msg() -> ExecuteMsg {
+        ExecuteMsg::Withdraw { secret: Binary::from(SECRET), immutables: None }
+    }
+
+    fn public_withdraw_msg() -> ExecuteMsg {
+        ExecuteMsg::PublicWithdraw { secret: Binary::from(SECRET), immutables: None }
+    }
+
+    fn transfers(res: &Response) -> Vec<(String, u128)> {
+        res.messages
+            .iter()
+            .map(|m| match &m.msg {
+                CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => {
+                    (to_address.clone(), amount[0].amount.u128())
+                }
+                msg => panic!("unexpected message {:?}", msg),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_withdraw_window() {
+        let (mut deps, env, immutables) = setup();
+        let taker = immutables.taker.as_str();
+
+        let err = execute(deps.as_mut(), after(&env, 9), mock_info(taker, &[]), withdraw_msg()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockNotReached {}));
+        let err = execute(deps.as_mut(), after(&env, 30), mock_info(taker, &[]), withdraw_msg()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockHasCrossed {}));
+
+        let res = execute(deps.as_mut(), after(&env, 10), mock_info(taker, &[]), withdraw_msg()).unwrap();
+        assert_eq!(transfers(&res), vec![(taker.to_string(), 1_000), (taker.to_string(), 100)]);
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), EscrowStatus::Withdrawn);
+    }
+
+    #[test]
+    fn test_withdraw_with_prefixed_hashlock() {
+        let mut deps = mock_bech32_dependencies();
+        let env = mock_env();
+        let mut immutables = src_immutables(&deps.api, &env);
+        immutables.hashlock = format!("0x{}", immutables.hashlock);
+        instantiate_escrow(&mut deps, &env, &immutables);
+
+        execute(deps.as_mut(), after(&env, 10), mock_info(&immutables.taker, &[]), withdraw_msg()).unwrap();
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), EscrowStatus::Withdrawn);
+    }
+
+    #[test]
+    fn test_withdraw_rejects_wrong_secret() {
+        let (mut deps, env, immutables) = setup();
+        let msg = ExecuteMsg::Withdraw { secret: Binary::from(b"wrong"), immutables: None };
+        let err = execute(deps.as_mut(), after(&env, 10), mock_info(&immutables.taker, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidSecret {}));
+    }
+
+    #[test]
+    fn test_withdraw_to_pays_target() {
+        let (mut deps, env, immutables) = setup();
+        let target = deps.api.addr_make("target").to_string();
+        let msg = ExecuteMsg::WithdrawTo {
+            secret: Binary::from(SECRET),
+            target: target.clone(),
+            immutables: None,
+        };
+
+        let err = execute(deps.as_mut(), after(&env, 30), mock_info(&immutables.taker, &[]), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockHasCrossed {}));
+
+        let res = execute(deps.as_mut(), after(&env, 29), mock_info(&immutables.taker, &[]), msg).unwrap();
+        assert_eq!(transfers(&res), vec![(target, 1_000), (immutables.taker, 100)]);
+    }
+
+    #[test]
+    fn test_public_withdraw_window() {
+        let (mut deps, env, immutables) = setup();
+        let resolver = deps.api.addr_make("resolver").to_string();
+
+        let err = execute(deps.as_mut(), after(&env, 19), mock_info(&resolver, &[]), public_withdraw_msg()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockNotReached {}));
+        let err = execute(deps.as_mut(), after(&env, 30), mock_info(&resolver, &[]), public_withdraw_msg()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockHasCrossed {}));
+
+        let res = execute(deps.as_mut(), after(&env, 20), mock_info(&resolver, &[]), public_withdraw_msg()).unwrap();
+        assert_eq!(transfers(&res), vec![(immutables.taker, 1_000), (resolver, 100)]);
+    }
+
+    #[test]
+    fn test_cancel_window() {
+        let (mut deps, env, immutables) = setup();
+        let msg = ExecuteMsg::Cancel { immutables: None };
+
+        let err = execute(deps.as_mut(), after(&env, 29), mock_info(&immutables.taker, &[]), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockNotReached {}));
+
+        let res = execute(deps.as_mut(), after(&env, 30), mock_info(&immutables.taker, &[]), msg).unwrap();
+        assert_eq!(transfers(&res), vec![(immutables.maker, 1_000), (immutables.taker, 100)]);
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), EscrowStatus::Cancelled);
+    }
+
+    #[test]
+    fn test_public_cancel_window() {
+        let (mut deps, env, immutables) = setup();
+        let resolver = deps.api.addr_make("resolver").to_string();
+        let msg = ExecuteMsg::PublicCancel { immutables: None };
+
+        let err = execute(deps.as_mut(), after(&env, 39), mock_info(&resolver, &[]), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockNotReached {}));
+
+        let res = execute(deps.as_mut(), after(&env, 40), mock_info(&resolver, &[]), msg).unwrap();
+        assert_eq!(transfers(&res), vec![(immutables.maker, 1_000), (resolver, 100)]);
+    }
+
+    #[test]
+    fn test_only_taker_can_withdraw_or_cancel() {
+        let (mut deps, env, _) = setup();
+        let maker = deps.api.addr_make("maker").to_string();
+
+        let err = execute(deps.as_mut(), after(&env, 10), mock_info(&maker, &[]), withdraw_msg()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let msg = ExecuteMsg::WithdrawTo {
+            secret: Binary::from(SECRET),
+            target: maker.clone(),
+            immutables: None,
+        };
+        let err = execute(deps.as_mut(), after(&env, 10), mock_info(&maker, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        let msg = ExecuteMsg::Cancel { immutables: None };
+        let err = execute(deps.as_mut(), after(&env, 30), mock_info(&maker, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn test_double_settlement_fails() {
+        let (mut deps, env, immutables) = setup();
+        let taker = immutables.taker.as_str();
+        execute(deps.as_mut(), after(&env, 10), mock_info(taker, &[]), withdraw_msg()).unwrap();
+
+        let err = execute(deps.as_mut(), after(&env, 20), mock_info(taker, &[]), public_withdraw_msg()).unwrap_err();
+        assert!(matches!(err, ContractError::EscrowAlreadyWithdrawn {}));
+        let msg = ExecuteMsg::PublicCancel { immutables: None };
+        let err = execute(deps.as_mut(), after(&env, 40), mock_info(taker, &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::EscrowAlreadyWithdrawn {}));
+    }
+
+    #[test]
+    fn test_rescue_funds() {
+        let (mut deps, env, immutables) = setup();
+        let msg = ExecuteMsg::RescueFunds {
+            token: "uatom".to_string(),
+            amount: Uint256::from(5u128),
+            immutables: None,
+        };
+        let delay = RESCUE_DELAY_SECONDS as u64;
+
+        let err = execute(deps.as_mut(), after(&env, delay - 1), mock_info(&immutables.taker, &[]), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::TimelockNotReached {}));
+        let err = execute(deps.as_mut(), after(&env, delay), mock_info(&immutables.maker, &[]), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), after(&env, delay), mock_info(&immutables.taker, &[]), msg).unwrap();
+        assert_eq!(transfers(&res), vec![(immutables.taker, 5)]);
+        assert_eq!(STATUS.load(&deps.storage).unwrap(), EscrowStatus::Rescued);
+    }
+
+    #[test]
+    fn test_balance_includes_escrowed_token() {
+        let (mut deps, env, _) = setup();
+        deps.querier.update_balance(env.contract.address.clone(), coins(1_100, "uatom"));
+
+        let res = query(deps.as_ref(), env, QueryMsg::Balance {}).unwrap();
+        let balance: BalanceResponse = from_json(res).unwrap();
+        assert_eq!(balance.balances, coins(1_100, "uatom"));
+        assert_eq!(balance.token, Some(Asset::new(AssetInfo::native("uatom"), 1_100u128)));
+    }
+}
@@ -0,0 +1,6 @@
+use cosmwasm_std::Addr;
+use cw_storage_plus::Item;
+pub use shared::escrow::{IMMUTABLES, IMMUTABLE_HASH, STATUS};
+pub const SAFETY_DEPOSIT_TOKEN: Item<String> = Item::new("safety_deposit_denom");
+pub const RESCUE_DELAY: Item<u32> = Item::new("rescue_delay");
+pub const FACTORY: Item<Addr> = Item::new("factory");
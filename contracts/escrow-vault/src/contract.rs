@@ -0,0 +1,489 @@
+use cosmwasm_std::{to_json_binary, Uint256};
+use cosmwasm_std::{
+    entry_point, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage,
+};
+use cw2::set_contract_version;
+
+use shared::asset::{Asset, AssetInfo};
+use shared::fee::{check_escrow_fees, withdrawal_msgs};
+use shared::types::Immutables;
+use shared::types::{EscrowStatus, TimelockStage};
+use shared::error::ContractError;
+use interfaces::escrow_vault::{ConfigResponse, EscrowRecord, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::state::{
+    ESCROWS, FEE_CAPS, FEE_TREASURY, RESCUE_DELAY, SAFETY_DEPOSIT_TOKEN, TIMELOCK_WINDOWS,
+};
+
+use shared::utils::{validate_rescue_delay, validate_secret, validate_token_amounts};
+
+const CONTRACT_NAME: &str = "crates.io:escrow-vault";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    SAFETY_DEPOSIT_TOKEN.save(deps.storage, &msg.safety_deposit_denom)?;
+    validate_rescue_delay(msg.rescue_delay)?;
+    RESCUE_DELAY.save(deps.storage, &msg.rescue_delay)?;
+    let min_timelock_windows = msg.min_timelock_windows.unwrap_or_default();
+    min_timelock_windows.validate(msg.rescue_delay)?;
+    TIMELOCK_WINDOWS.save(deps.storage, &min_timelock_windows)?;
+    let fee_caps = msg.fee_caps.unwrap_or_default();
+    fee_caps.validate()?;
+    FEE_CAPS.save(deps.storage, &fee_caps)?;
+    if let Some(fee_treasury) = msg.fee_treasury {
+        FEE_TREASURY.save(deps.storage, &deps.api.addr_validate(&fee_treasury)?)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("contract", "escrow-vault"))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::CreateEscrow { immutables, src_cancellation_timestamp } => {
+            create_escrow(deps, env, info, *immutables, src_cancellation_timestamp)
+        }
+        ExecuteMsg::Withdraw { immutables_hash, secret } => withdraw(deps, env, info, immutables_hash, secret),
+        ExecuteMsg::PublicWithdraw { immutables_hash, secret } => public_withdraw(deps, env, info, immutables_hash, secret),
+        ExecuteMsg::Cancel { immutables_hash } => cancel(deps, env, info, immutables_hash),
+        ExecuteMsg::RescueFunds { immutables_hash } => rescue_funds(deps, env, info, immutables_hash),
+    }
+}
+
+/// Locks the funds sent with the message under the immutables hash, with the
+/// same checks the factory applies before deploying an EscrowDst
+pub fn create_escrow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    mut immutables: Immutables,
+    src_cancellation_timestamp: u64,
+) -> Result<Response, ContractError> {
+    let safety_deposit_token = SAFETY_DEPOSIT_TOKEN.load(deps.storage)?;
+    validate_token_amounts(&immutables, &info, &safety_deposit_token)?;
+
+    let rescue_delay = match immutables.rescue_delay {
+        Some(rescue_delay) => {
+            validate_rescue_delay(rescue_delay)?;
+            rescue_delay
+        }
+        None => RESCUE_DELAY.load(deps.storage)?,
+    };
+    immutables
+        .timelocks
+        .validate_rescue_delay(rescue_delay, TimelockStage::DstCancellation)?;
+
+    check_escrow_fees(
+        deps.api,
+        &immutables,
+        &FEE_CAPS.load(deps.storage)?,
+        FEE_TREASURY.may_load(deps.storage)?.as_ref(),
+    )?;
+
+    immutables
+        .timelocks
+        .validate(&TIMELOCK_WINDOWS.load(deps.storage)?)?;
+
+    immutables.timelocks.set_deployed_at(env.block.time);
+
+    if immutables.timelocks.get_timelock(TimelockStage::DstCancellation) > src_cancellation_timestamp {
+        return Err(ContractError::InvalidCreationTime {});
+    }
+
+    let immutables_hash = immutables.compute_immutables_hash()?;
+    if ESCROWS.has(deps.storage, &immutables_hash) {
+        return Err(ContractError::EscrowAlreadyExists {});
+    }
+
+    ESCROWS.save(
+        deps.storage,
+        &immutables_hash,
+        &EscrowRecord {
+            immutables: immutables.clone(),
+            status: EscrowStatus::Active,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("method", "create_escrow")
+        .add_attribute("immutables_hash", immutables_hash)
+        .add_attribute("deployed_at", immutables.timelocks.deployed_at.to_string())
+        .add_attribute("hashlock", immutables.hashlock)
+        .add_attribute("taker", immutables.taker))
+}
+
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables_hash: String,
+    secret: Binary,
+) -> Result<Response, ContractError> {
+    let immutables = ESCROWS.load(deps.storage, &immutables_hash)?.immutables;
+
+    if info.sender != immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::DstWithdrawal) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+    if env.block.time.seconds() >= immutables.timelocks.get_timelock(TimelockStage::DstCancellation) {
+        return Err(ContractError::TimelockHasCrossed {});
+    }
+
+    _withdraw(deps, &info, &immutables_hash, secret, &immutables)
+}
+
+pub fn public_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables_hash: String,
+    secret: Binary,
+) -> Result<Response, ContractError> {
+    let immutables = ESCROWS.load(deps.storage, &immutables_hash)?.immutables;
+
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::DstPublicWithdrawal) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+    if env.block.time.seconds() >= immutables.timelocks.get_timelock(TimelockStage::DstCancellation) {
+        return Err(ContractError::TimelockHasCrossed {});
+    }
+
+    _withdraw(deps, &info, &immutables_hash, secret, &immutables)
+}
+
+pub fn cancel(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables_hash: String,
+) -> Result<Response, ContractError> {
+    let immutables = ESCROWS.load(deps.storage, &immutables_hash)?.immutables;
+
+    if info.sender != immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if env.block.time.seconds() < immutables.timelocks.get_timelock(TimelockStage::DstCancellation) {
+        return Err(ContractError::TimelockNotReached {});
+    }
+
+    update_status(deps.storage, &immutables_hash, EscrowStatus::Cancelled)?;
+
+    let messages = refund_msgs(deps.as_ref(), &immutables, info.sender.as_str())?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "cancel")
+        .add_attribute("immutables_hash", immutables_hash))
+}
+
+pub fn rescue_funds(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    immutables_hash: String,
+) -> Result<Response, ContractError> {
+    let EscrowRecord { immutables, status } = ESCROWS.load(deps.storage, &immutables_hash)?;
+
+    if info.sender != immutables.taker {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // the vault pools all escrows, so only the funds of an unsettled escrow can be rescued
+    if status != EscrowStatus::Active {
+        return Err(ContractError::InvalidStatusTransition {
+            from: status.to_string(),
+            to: EscrowStatus::Rescued.to_string(),
+        });
+    }
+
+    let rescue_delay = match immutables.rescue_delay {
+        Some(rescue_delay) => rescue_delay,
+        None => RESCUE_DELAY.load(deps.storage)?,
+    };
+    // the rescue must not cut short the cancellation of the escrow
+    let rescue_start = immutables
+        .timelocks
+        .get_timelock(TimelockStage::RescueDelay(rescue_delay))
+        .max(immutables.timelocks.get_timelock(TimelockStage::DstCancellation));
+    if env.block.time.seconds() < rescue_start {
+        return Err(ContractError::TimelockNotReached {});
+    }
+
+    update_status(deps.storage, &immutables_hash, EscrowStatus::Rescued)?;
+
+    let messages = refund_msgs(deps.as_ref(), &immutables, &immutables.taker)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "rescue_funds")
+        .add_attribute("immutables_hash", immutables_hash))
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Escrow { immutables_hash } => to_json_binary(&ESCROWS.load(deps.storage, &immutables_hash)?),
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    Ok(ConfigResponse {
+        safety_deposit_denom: SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
+        rescue_delay: RESCUE_DELAY.load(deps.storage)?,
+        min_timelock_windows: TIMELOCK_WINDOWS.load(deps.storage)?,
+        fee_caps: FEE_CAPS.load(deps.storage)?,
+        fee_treasury: FEE_TREASURY.may_load(deps.storage)?,
+    })
+}
+
+/// Moves the escrow stored under `immutables_hash` to `next`, rejecting double settlement
+fn update_status(
+    storage: &mut dyn Storage,
+    immutables_hash: &str,
+    next: EscrowStatus,
+) -> Result<(), ContractError> {
+    let mut record = ESCROWS.load(storage, immutables_hash)?;
+    record.status = record.status.transition(next)?;
+    ESCROWS.save(storage, immutables_hash, &record)?;
+    Ok(())
+}
+
+/// Transfers of the locked tokens to the taker and of the safety deposit to
+/// `safety_deposit_recipient`
+fn refund_msgs(
+    deps: Deps,
+    immutables: &Immutables,
+    safety_deposit_recipient: &str,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut messages = vec![];
+
+    if immutables.amount > Uint256::zero() {
+        let token = AssetInfo::from_token(deps.api, &immutables.token);
+        messages.push(Asset::from_uint256(token, immutables.amount)?.transfer_msg(&immutables.taker)?);
+    }
+
+    if immutables.safety_deposit > Uint256::zero() {
+        let safety_deposit = AssetInfo::native(SAFETY_DEPOSIT_TOKEN.load(deps.storage)?);
+        messages.push(Asset::from_uint256(safety_deposit, immutables.safety_deposit)?.transfer_msg(safety_deposit_recipient)?);
+    }
+
+    Ok(messages)
+}
+
+/// Internal withdraw function that handles fee distribution and token transfers
+fn _withdraw(
+    deps: DepsMut,
+    info: &MessageInfo,
+    immutables_hash: &str,
+    secret: Binary,
+    immutables: &Immutables,
+) -> Result<Response, ContractError> {
+    if !validate_secret(&secret, &immutables.hashlock) {
+        return Err(ContractError::InvalidSecret {});
+    }
+
+    update_status(deps.storage, immutables_hash, EscrowStatus::Withdrawn)?;
+
+    let (messages, fees) = withdrawal_msgs(
+        deps.api,
+        immutables,
+        FEE_TREASURY.may_load(deps.storage)?.as_ref(),
+        &SAFETY_DEPOSIT_TOKEN.load(deps.storage)?,
+        &info.sender,
+    )?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("method", "withdraw")
+        .add_attribute("immutables_hash", immutables_hash)
+        .add_attribute("secret", secret.to_string())
+        .add_attribute("maker", immutables.maker.clone())
+        .add_attributes(fees.attributes()?)
+        .add_attribute("safety_deposit", immutables.safety_deposit.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{coins, OwnedDeps};
+    use shared::{keccak256, FeeCaps};
+    use shared::testing::{attribute, mock_immutables};
+
+    const SECRET: &[u8] = b"secret";
+    const RESCUE_DELAY_SECONDS: u32 = 3600;
+
+    type TestDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+    /// Vault holding one active escrow, returning its immutables hash and taker
+    fn setup() -> (TestDeps, Env, String, String) {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let taker = deps.api.addr_make("resolver").to_string();
+
+        let msg = InstantiateMsg {
+            safety_deposit_denom: "ustake".to_string(),
+            rescue_delay: RESCUE_DELAY_SECONDS,
+            min_timelock_windows: None,
+            fee_caps: None,
+            fee_treasury: None,
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info(&taker, &[]), msg).unwrap();
+
+        let immutables = mock_immutables(keccak256(SECRET));
+        let msg = ExecuteMsg::CreateEscrow {
+            immutables: Box::new(immutables),
+            src_cancellation_timestamp: env.block.time.seconds() + 100,
+        };
+        let funds = [coins(1_000, "uatom"), coins(100, "ustake")].concat();
+        let response = execute(deps.as_mut(), env.clone(), mock_info(&taker, &funds), msg).unwrap();
+        let immutables_hash = attribute(&response, "immutables_hash");
+
+        (deps, env, immutables_hash, taker)
+    }
+
+    fn after(env: &Env, seconds: u64) -> Env {
+        let mut env = env.clone();
+        env.block.time = env.block.time.plus_seconds(seconds);
+        env
+    }
+
+    fn rescue(deps: &mut TestDeps, env: &Env, taker: &str, immutables_hash: &str) -> Result<Response, ContractError> {
+        let msg = ExecuteMsg::RescueFunds {
+            immutables_hash: immutables_hash.to_string(),
+        };
+        execute(deps.as_mut(), after(env, RESCUE_DELAY_SECONDS as u64), mock_info(taker, &[]), msg)
+    }
+
+    #[test]
+    fn test_rescue_after_withdraw_fails() {
+        let (mut deps, env, immutables_hash, taker) = setup();
+        let msg = ExecuteMsg::Withdraw {
+            immutables_hash: immutables_hash.clone(),
+            secret: Binary::from(SECRET),
+        };
+        execute(deps.as_mut(), after(&env, 15), mock_info(&taker, &[]), msg).unwrap();
+
+        let err = rescue(&mut deps, &env, &taker, &immutables_hash).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidStatusTransition { .. }));
+    }
+
+    #[test]
+    fn test_rescue_after_cancel_fails() {
+        let (mut deps, env, immutables_hash, taker) = setup();
+        let msg = ExecuteMsg::Cancel {
+            immutables_hash: immutables_hash.clone(),
+        };
+        execute(deps.as_mut(), after(&env, 30), mock_info(&taker, &[]), msg).unwrap();
+
+        let err = rescue(&mut deps, &env, &taker, &immutables_hash).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidStatusTransition { .. }));
+    }
+
+    #[test]
+    fn test_second_rescue_fails() {
+        let (mut deps, env, immutables_hash, taker) = setup();
+        let response = rescue(&mut deps, &env, &taker, &immutables_hash).unwrap();
+        assert_eq!(response.messages.len(), 2);
+
+        let err = rescue(&mut deps, &env, &taker, &immutables_hash).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidStatusTransition { .. }));
+    }
+
+    #[test]
+    fn test_create_escrow_requires_exact_funds() {
+        let (mut deps, env, _, taker) = setup();
+        let mut immutables = mock_immutables(keccak256(SECRET));
+        immutables.order_hash = "33".repeat(32);
+
+        let overpaid = [coins(1_001, "uatom"), coins(100, "ustake")].concat();
+        let extra_denom = [coins(1_000, "uatom"), coins(100, "ustake"), coins(1, "uosmo")].concat();
+        for funds in [overpaid, extra_denom] {
+            let msg = ExecuteMsg::CreateEscrow {
+                immutables: Box::new(immutables.clone()),
+                src_cancellation_timestamp: env.block.time.seconds() + 100,
+            };
+            let err = execute(deps.as_mut(), env.clone(), mock_info(&taker, &funds), msg).unwrap_err();
+            assert!(matches!(err, ContractError::UnexpectedTokenAmount { .. }));
+        }
+    }
+
+    #[test]
+    fn test_create_escrow_rejects_rescue_before_cancellation() {
+        let (mut deps, env, _, taker) = setup();
+        let mut immutables = mock_immutables(keccak256(SECRET));
+        immutables.timelocks.dst_cancellation = 7200;
+        immutables.rescue_delay = Some(3600);
+
+        let msg = ExecuteMsg::CreateEscrow {
+            immutables: Box::new(immutables),
+            src_cancellation_timestamp: env.block.time.seconds() + 10_000,
+        };
+        let funds = [coins(1_000, "uatom"), coins(100, "ustake")].concat();
+        let err = execute(deps.as_mut(), env, mock_info(&taker, &funds), msg).unwrap_err();
+        assert!(matches!(err, ContractError::RescueDelayTooShort { rescue_delay: 3600, .. }));
+    }
+
+    #[test]
+    fn test_create_escrow_enforces_fee_caps_and_treasury() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let taker = deps.api.addr_make("resolver").to_string();
+        let treasury = deps.api.addr_make("treasury").to_string();
+        let msg = InstantiateMsg {
+            safety_deposit_denom: "ustake".to_string(),
+            rescue_delay: RESCUE_DELAY_SECONDS,
+            min_timelock_windows: None,
+            fee_caps: Some(FeeCaps {
+                max_protocol_fee_bps: 100,
+                ..FeeCaps::default()
+            }),
+            fee_treasury: Some(treasury.clone()),
+        };
+        instantiate(deps.as_mut(), env.clone(), mock_info(&taker, &[]), msg).unwrap();
+
+        let funds = [coins(1_000, "uatom"), coins(100, "ustake")].concat();
+        let mut create = |protocol_fee: u128, recipient: &str| {
+            let mut immutables = mock_immutables(keccak256(SECRET));
+            immutables.parameters = format!(
+                r#"{{"protocol_fee_amount":"{protocol_fee}","integrator_fee_amount":"0","protocol_fee_recipient":"{recipient}","integrator_fee_recipient":""}}"#
+            )
+            .into_bytes();
+            let msg = ExecuteMsg::CreateEscrow {
+                immutables: Box::new(immutables),
+                src_cancellation_timestamp: env.block.time.seconds() + 100,
+            };
+            execute(deps.as_mut(), env.clone(), mock_info(&taker, &funds), msg)
+        };
+
+        let other = MockApi::default().addr_make("other").to_string();
+        assert!(matches!(
+            create(10, &other),
+            Err(ContractError::InvalidFeeRecipient { .. })
+        ));
+        assert!(matches!(
+            create(20, &treasury),
+            Err(ContractError::FeeCapExceeded { .. })
+        ));
+        create(10, &treasury).unwrap();
+    }
+}
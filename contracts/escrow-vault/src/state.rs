@@ -0,0 +1,12 @@
+use cw_storage_plus::{Item, Map};
+use interfaces::escrow_vault::EscrowRecord;
+use cosmwasm_std::Addr;
+use shared::{FeeCaps, TimelockWindows};
+pub const SAFETY_DEPOSIT_TOKEN: Item<String> = Item::new("safety_deposit_denom");
+pub const RESCUE_DELAY: Item<u32> = Item::new("rescue_delay");
+pub const TIMELOCK_WINDOWS: Item<TimelockWindows> = Item::new("timelock_windows");
+pub const FEE_CAPS: Item<FeeCaps> = Item::new("fee_caps");
+/// Required recipient of protocol fees, if set
+pub const FEE_TREASURY: Item<Addr> = Item::new("fee_treasury");
+/// Escrows held by the vault, keyed by immutables hash
+pub const ESCROWS: Map<&str, EscrowRecord> = Map::new("escrows");
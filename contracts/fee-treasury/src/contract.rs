@@ -0,0 +1,590 @@
+use cosmwasm_std::{
+    entry_point, from_json, to_json_binary, Addr, Api, Binary, Deps, DepsMut, Env, MessageInfo,
+    Order as StorageOrder, Response, StdError, StdResult, Storage, Uint128,
+};
+use cw_storage_plus::{Bound, Map};
+use cw2::set_contract_version;
+use cw20::Cw20ReceiveMsg;
+
+use shared::asset::{Asset, AssetInfo};
+use shared::error::ContractError;
+use interfaces::fee_treasury::{
+    AccruedResponse, AllAccruedResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+    ReceiveMsg, Withdrawal, WithdrawalsResponse,
+};
+use crate::state::{
+    ACCRUED, CW20_TOKENS, DEPOSITORS, FACTORY, OWNER, PENDING_OWNER, WITHDRAWALS, WITHDRAWAL_COUNT,
+    WITHDRAWN,
+};
+
+const CONTRACT_NAME: &str = "crates.io:fee-treasury";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let owner = match msg.owner {
+        Some(owner) => deps.api.addr_validate(&owner)?,
+        None => info.sender,
+    };
+    OWNER.save(deps.storage, &owner)?;
+    WITHDRAWAL_COUNT.save(deps.storage, &0)?;
+    if let Some(factory) = msg.factory {
+        FACTORY.save(deps.storage, &deps.api.addr_validate(&factory)?)?;
+    }
+    DEPOSITORS.save(deps.storage, &validate_addrs(deps.api, &msg.depositors)?)?;
+    CW20_TOKENS.save(deps.storage, &validate_addrs(deps.api, &msg.cw20_tokens)?)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "instantiate")
+        .add_attribute("contract", "fee-treasury")
+        .add_attribute("owner", owner.to_string()))
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Deposit {} => deposit(deps, info),
+        ExecuteMsg::Receive(cw20_msg) => receive_cw20(deps, info, cw20_msg),
+        ExecuteMsg::Withdraw { asset, recipient } => withdraw(deps, env, info, asset, recipient),
+        ExecuteMsg::UpdateConfig {
+            factory,
+            clear_factory,
+            depositors,
+            cw20_tokens,
+        } => update_config(deps, info, factory, clear_factory, depositors, cw20_tokens),
+        ExecuteMsg::TransferOwnership { new_owner } => transfer_ownership(deps, info, new_owner),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+    }
+}
+
+/// Records the native funds sent with the message as accrued fees
+pub fn deposit(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(ContractError::MissingRequiredToken {
+            token: "any".to_string(),
+        });
+    }
+
+    validate_depositor(deps.as_ref(), &info.sender)?;
+
+    for coin in info.funds.iter() {
+        add_amount(deps.storage, ACCRUED, &AssetInfo::native(&coin.denom), coin.amount)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("method", "deposit")
+        .add_attribute("sender", info.sender.to_string()))
+}
+
+/// Records allowlisted CW20 tokens sent by the token contract `info.sender` as accrued fees
+pub fn receive_cw20(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_json(&cw20_msg.msg)? {
+        ReceiveMsg::Deposit {} => {
+            // the token contract reports the sender, so it has to be trusted
+            if !CW20_TOKENS.load(deps.storage)?.contains(&info.sender) {
+                return Err(ContractError::UnsupportedFeeToken {
+                    token: info.sender.to_string(),
+                });
+            }
+            validate_depositor(deps.as_ref(), &deps.api.addr_validate(&cw20_msg.sender)?)?;
+
+            let asset = AssetInfo::Cw20 {
+                contract_addr: info.sender,
+            };
+            add_amount(deps.storage, ACCRUED, &asset, cw20_msg.amount)?;
+
+            Ok(Response::new()
+                .add_attribute("method", "deposit")
+                .add_attribute("asset", asset.to_string())
+                .add_attribute("sender", cw20_msg.sender))
+        }
+    }
+}
+
+/// Sends accrued fees to `recipient` and records the withdrawal
+pub fn withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: Asset,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    validate_owner(deps.as_ref(), &info.sender)?;
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    let available = query_accrued(deps.as_ref(), asset.info.clone())?.available;
+    if available < asset.amount {
+        return Err(ContractError::InsufficientTokenAmount {
+            token: asset.info.to_string(),
+            expected: asset.amount.to_string(),
+            actual: available.to_string(),
+        });
+    }
+    add_amount(deps.storage, WITHDRAWN, &asset.info, asset.amount)?;
+
+    let id = WITHDRAWAL_COUNT.load(deps.storage)? + 1;
+    WITHDRAWAL_COUNT.save(deps.storage, &id)?;
+    WITHDRAWALS.save(
+        deps.storage,
+        id,
+        &Withdrawal {
+            id,
+            asset: asset.clone(),
+            recipient: recipient.clone(),
+            height: env.block.height,
+            timestamp: env.block.time.seconds(),
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(asset.transfer_msg(&recipient)?)
+        .add_attribute("method", "withdraw")
+        .add_attribute("withdrawal_id", id.to_string())
+        .add_attribute("asset", asset.info.to_string())
+        .add_attribute("amount", asset.amount.to_string())
+        .add_attribute("recipient", recipient.to_string()))
+}
+
+/// Updates who can deposit fees and in which CW20 tokens
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    factory: Option<String>,
+    clear_factory: bool,
+    depositors: Option<Vec<String>>,
+    cw20_tokens: Option<Vec<String>>,
+) -> Result<Response, ContractError> {
+    validate_owner(deps.as_ref(), &info.sender)?;
+
+    if clear_factory && factory.is_some() {
+        return Err(StdError::generic_err("Cannot both set and clear the factory").into());
+    }
+
+    if let Some(factory) = factory {
+        FACTORY.save(deps.storage, &deps.api.addr_validate(&factory)?)?;
+    }
+    if clear_factory {
+        FACTORY.remove(deps.storage);
+    }
+    if let Some(depositors) = depositors {
+        DEPOSITORS.save(deps.storage, &validate_addrs(deps.api, &depositors)?)?;
+    }
+    if let Some(cw20_tokens) = cw20_tokens {
+        CW20_TOKENS.save(deps.storage, &validate_addrs(deps.api, &cw20_tokens)?)?;
+    }
+
+    Ok(Response::new().add_attribute("method", "update_config"))
+}
+
+/// Proposes a new owner, replacing any pending proposal
+pub fn transfer_ownership(
+    deps: DepsMut,
+    info: MessageInfo,
+    new_owner: String,
+) -> Result<Response, ContractError> {
+    validate_owner(deps.as_ref(), &info.sender)?;
+
+    let new_owner = deps.api.addr_validate(&new_owner)?;
+    PENDING_OWNER.save(deps.storage, &new_owner)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "transfer_ownership")
+        .add_attribute("pending_owner", new_owner.to_string()))
+}
+
+/// Completes an ownership transfer, called by the proposed owner
+pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if PENDING_OWNER.may_load(deps.storage)?.as_ref() != Some(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    PENDING_OWNER.remove(deps.storage);
+    OWNER.save(deps.storage, &info.sender)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "accept_ownership")
+        .add_attribute("owner", info.sender.to_string()))
+}
+
+/// Adds `amount` to the running total of `asset` in `totals`
+fn add_amount(
+    storage: &mut dyn Storage,
+    totals: Map<&str, (AssetInfo, Uint128)>,
+    asset: &AssetInfo,
+    amount: Uint128,
+) -> StdResult<()> {
+    let key = asset.to_string();
+    let total = totals
+        .may_load(storage, &key)?
+        .map_or(Uint128::zero(), |(_, total)| total);
+    totals.save(storage, &key, &(asset.clone(), total.checked_add(amount)?))
+}
+
+/// Check that `depositor` is a configured depositor or a contract instantiated by the factory
+fn validate_depositor(deps: Deps, depositor: &Addr) -> Result<(), ContractError> {
+    if DEPOSITORS.load(deps.storage)?.contains(depositor) {
+        return Ok(());
+    }
+    if let Some(factory) = FACTORY.may_load(deps.storage)? {
+        // accounts have no contract info, so the query fails for them
+        let created_by_factory = deps
+            .querier
+            .query_wasm_contract_info(depositor)
+            .is_ok_and(|contract_info| contract_info.creator == factory.as_str());
+        if created_by_factory {
+            return Ok(());
+        }
+    }
+    Err(ContractError::UnauthorizedDepositor {
+        depositor: depositor.to_string(),
+    })
+}
+
+fn validate_addrs(api: &dyn Api, addrs: &[String]) -> StdResult<Vec<Addr>> {
+    addrs.iter().map(|addr| api.addr_validate(addr)).collect()
+}
+
+fn validate_owner(deps: Deps, sender: &Addr) -> Result<(), ContractError> {
+    if OWNER.load(deps.storage)? != *sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Accrued { asset } => to_json_binary(&query_accrued(deps, asset)?),
+        QueryMsg::AllAccrued { start_after, limit } => {
+            to_json_binary(&query_all_accrued(deps, start_after, limit)?)
+        }
+        QueryMsg::Withdrawals { start_after, limit } => {
+            to_json_binary(&query_withdrawals(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    Ok(ConfigResponse {
+        owner: OWNER.load(deps.storage)?,
+        pending_owner: PENDING_OWNER.may_load(deps.storage)?,
+        factory: FACTORY.may_load(deps.storage)?,
+        depositors: DEPOSITORS.load(deps.storage)?,
+        cw20_tokens: CW20_TOKENS.load(deps.storage)?,
+    })
+}
+
+fn query_accrued(deps: Deps, asset: AssetInfo) -> StdResult<AccruedResponse> {
+    let key = asset.to_string();
+    let total = |totals: Map<&str, (AssetInfo, Uint128)>| -> StdResult<Uint128> {
+        Ok(totals
+            .may_load(deps.storage, &key)?
+            .map_or(Uint128::zero(), |(_, total)| total))
+    };
+    let accrued = total(ACCRUED)?;
+    let withdrawn = total(WITHDRAWN)?;
+
+    Ok(AccruedResponse {
+        asset,
+        accrued,
+        withdrawn,
+        available: accrued.checked_sub(withdrawn)?,
+    })
+}
+
+fn query_all_accrued(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AllAccruedResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let accrued = ACCRUED
+        .range(deps.storage, start, None, StorageOrder::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (_, (asset, _)) = item?;
+            query_accrued(deps, asset)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllAccruedResponse { accrued })
+}
+
+fn query_withdrawals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<WithdrawalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let withdrawals = WITHDRAWALS
+        .range(deps.storage, start, None, StorageOrder::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, withdrawal)| withdrawal))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(WithdrawalsResponse { withdrawals })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+    use cosmwasm_std::{
+        coins, BankMsg, Coin, ContractInfoResponse, ContractResult, CosmosMsg, OwnedDeps,
+        SystemError, SystemResult, WasmQuery,
+    };
+
+    type TestDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+    /// Treasury of the factory, with the vault as depositor and `token` accepted.
+    /// Only the `escrow` contract was instantiated by the factory.
+    fn setup() -> (TestDeps, Addr) {
+        let mut deps = mock_dependencies();
+        let factory = deps.api.addr_make("factory");
+        let escrow = deps.api.addr_make("escrow");
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::ContractInfo { contract_addr } if *contract_addr == escrow => {
+                let mut contract_info = ContractInfoResponse::default();
+                contract_info.creator = factory.to_string();
+                SystemResult::Ok(ContractResult::Ok(to_json_binary(&contract_info).unwrap()))
+            }
+            _ => SystemResult::Err(SystemError::NoSuchContract {
+                addr: "unknown".to_string(),
+            }),
+        });
+
+        let owner = deps.api.addr_make("owner");
+        let msg = InstantiateMsg {
+            owner: None,
+            factory: Some(deps.api.addr_make("factory").to_string()),
+            depositors: vec![deps.api.addr_make("vault").to_string()],
+            cw20_tokens: vec![deps.api.addr_make("token").to_string()],
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info(owner.as_str(), &[]), msg).unwrap();
+        (deps, owner)
+    }
+
+    fn cw20_deposit_msg(sender: &Addr, amount: u128) -> ExecuteMsg {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&ReceiveMsg::Deposit {}).unwrap(),
+        })
+    }
+
+    fn all_accrued(deps: &TestDeps, start_after: Option<String>, limit: Option<u32>) -> Vec<(String, u128)> {
+        let res: AllAccruedResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::AllAccrued { start_after, limit }).unwrap()).unwrap();
+        res.accrued
+            .iter()
+            .map(|accrued| (accrued.asset.to_string(), accrued.accrued.u128()))
+            .collect()
+    }
+
+    fn accrued(deps: &TestDeps, asset: AssetInfo) -> AccruedResponse {
+        from_json(query(deps.as_ref(), mock_env(), QueryMsg::Accrued { asset }).unwrap()).unwrap()
+    }
+
+    fn withdraw_msg(amount: u128, recipient: &Addr) -> ExecuteMsg {
+        ExecuteMsg::Withdraw {
+            asset: Asset::new(AssetInfo::native("uatom"), amount),
+            recipient: recipient.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_deposits_accrue() {
+        let (mut deps, _) = setup();
+        let escrow = deps.api.addr_make("escrow");
+        let token = deps.api.addr_make("token");
+
+        // plain transfers are not fees
+        deps.querier.update_balance(mock_env().contract.address, coins(500, "uatom"));
+        assert_eq!(accrued(&deps, AssetInfo::native("uatom")).accrued, Uint128::zero());
+
+        let info = mock_info(escrow.as_str(), &coins(100, "uatom"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+        let msg = cw20_deposit_msg(&escrow, 30);
+        execute(deps.as_mut(), mock_env(), mock_info(token.as_str(), &[]), msg).unwrap();
+
+        let totals = all_accrued(&deps, None, None);
+        assert_eq!(totals.len(), 2);
+        assert!(totals.contains(&("uatom".to_string(), 100)));
+        assert!(totals.contains(&(token.to_string(), 30)));
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info(escrow.as_str(), &[]), ExecuteMsg::Deposit {})
+            .unwrap_err();
+        assert!(matches!(err, ContractError::MissingRequiredToken { .. }));
+    }
+
+    #[test]
+    fn test_deposits_only_from_escrows_and_depositors() {
+        let (mut deps, owner) = setup();
+        let escrow = deps.api.addr_make("escrow");
+        let vault = deps.api.addr_make("vault");
+        let attacker = deps.api.addr_make("attacker");
+        let token = deps.api.addr_make("token");
+
+        let info = mock_info(attacker.as_str(), &coins(100, "uatom"));
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap_err();
+        assert!(matches!(err, ContractError::UnauthorizedDepositor { .. }));
+        let msg = cw20_deposit_msg(&attacker, 30);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(token.as_str(), &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::UnauthorizedDepositor { .. }));
+
+        // a fake CW20 can claim any sender
+        let fake_token = deps.api.addr_make("fake_token");
+        let msg = cw20_deposit_msg(&escrow, 1_000_000);
+        let err = execute(deps.as_mut(), mock_env(), mock_info(fake_token.as_str(), &[]), msg).unwrap_err();
+        assert!(matches!(err, ContractError::UnsupportedFeeToken { .. }));
+        assert!(all_accrued(&deps, None, None).is_empty());
+
+        let info = mock_info(vault.as_str(), &coins(100, "uatom"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            factory: None,
+            clear_factory: true,
+            depositors: None,
+            cw20_tokens: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), mock_info(attacker.as_str(), &[]), msg.clone()).unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+        execute(deps.as_mut(), mock_env(), mock_info(owner.as_str(), &[]), msg).unwrap();
+
+        let info = mock_info(escrow.as_str(), &coins(100, "uatom"));
+        let err = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap_err();
+        assert!(matches!(err, ContractError::UnauthorizedDepositor { .. }));
+        assert_eq!(all_accrued(&deps, None, None), vec![("uatom".to_string(), 100)]);
+    }
+
+    #[test]
+    fn test_all_accrued_paginates() {
+        let (mut deps, _) = setup();
+        let denoms = ["uatom", "uosmo", "ustake"];
+        for denom in denoms {
+            let info = mock_info(deps.api.addr_make("vault").as_str(), &coins(10, denom));
+            execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+        }
+
+        let page = all_accrued(&deps, None, Some(2));
+        assert_eq!(page, vec![("uatom".to_string(), 10), ("uosmo".to_string(), 10)]);
+        let page = all_accrued(&deps, Some("uosmo".to_string()), Some(2));
+        assert_eq!(page, vec![("ustake".to_string(), 10)]);
+    }
+
+    #[test]
+    fn test_withdraw() {
+        let (mut deps, owner) = setup();
+        let recipient = deps.api.addr_make("recipient");
+        let info = mock_info(deps.api.addr_make("escrow").as_str(), &coins(100, "uatom"));
+        execute(deps.as_mut(), mock_env(), info, ExecuteMsg::Deposit {}).unwrap();
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(recipient.as_str(), &[]),
+            withdraw_msg(40, &recipient),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_str(), &[]),
+            withdraw_msg(40, &recipient),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: vec![Coin::new(40, "uatom")],
+            })
+        );
+
+        let accrued = accrued(&deps, AssetInfo::native("uatom"));
+        assert_eq!(accrued.accrued, Uint128::new(100));
+        assert_eq!(accrued.withdrawn, Uint128::new(40));
+        assert_eq!(accrued.available, Uint128::new(60));
+
+        let res: WithdrawalsResponse = from_json(
+            query(deps.as_ref(), mock_env(), QueryMsg::Withdrawals { start_after: None, limit: None }).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.withdrawals.len(), 1);
+        assert_eq!(res.withdrawals[0].id, 1);
+        assert_eq!(res.withdrawals[0].recipient, recipient);
+
+        // only what is left of the accrued fees can be withdrawn
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_str(), &[]),
+            withdraw_msg(61, &recipient),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::InsufficientTokenAmount { .. }));
+    }
+
+    #[test]
+    fn test_transfer_ownership() {
+        let (mut deps, owner) = setup();
+        let new_owner = deps.api.addr_make("multisig");
+        let msg = ExecuteMsg::TransferOwnership {
+            new_owner: new_owner.to_string(),
+        };
+
+        let err = execute(deps.as_mut(), mock_env(), mock_info(new_owner.as_str(), &[]), msg.clone())
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(deps.as_mut(), mock_env(), mock_info(owner.as_str(), &[]), msg).unwrap();
+        let err = execute(deps.as_mut(), mock_env(), mock_info(owner.as_str(), &[]), ExecuteMsg::AcceptOwnership {})
+            .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+
+        execute(deps.as_mut(), mock_env(), mock_info(new_owner.as_str(), &[]), ExecuteMsg::AcceptOwnership {})
+            .unwrap();
+        let config: ConfigResponse =
+            from_json(query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap()).unwrap();
+        assert_eq!(config.owner, new_owner);
+        assert_eq!(config.pending_owner, None);
+
+        // the previous owner can no longer withdraw
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(owner.as_str(), &[]),
+            withdraw_msg(1, &owner),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Unauthorized {}));
+    }
+}
@@ -0,0 +1,19 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+use interfaces::fee_treasury::Withdrawal;
+use shared::AssetInfo;
+pub const OWNER: Item<Addr> = Item::new("owner");
+pub const PENDING_OWNER: Item<Addr> = Item::new("pending_owner");
+/// Factory whose escrows may deposit fees, when set
+pub const FACTORY: Item<Addr> = Item::new("factory");
+/// Other contracts allowed to deposit fees, e.g. an escrow vault
+pub const DEPOSITORS: Item<Vec<Addr>> = Item::new("depositors");
+/// CW20 tokens accepted as fees
+pub const CW20_TOKENS: Item<Vec<Addr>> = Item::new("cw20_tokens");
+/// Total deposited per asset, keyed by denom or CW20 contract address
+pub const ACCRUED: Map<&str, (AssetInfo, Uint128)> = Map::new("accrued");
+/// Total withdrawn per asset, keyed by denom or CW20 contract address
+pub const WITHDRAWN: Map<&str, (AssetInfo, Uint128)> = Map::new("withdrawn");
+/// Log of every withdrawal, keyed by id
+pub const WITHDRAWALS: Map<u64, Withdrawal> = Map::new("withdrawals");
+pub const WITHDRAWAL_COUNT: Item<u64> = Item::new("withdrawal_count");
@@ -1,39 +1,69 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{Addr, Binary, Uint256};
-use shared::Immutables;
+use cosmwasm_std::{Addr, Binary, Coin, HexBinary, Uint256};
+use shared::{Asset, EscrowStatus, Immutables};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub safety_deposit_denom: String,
     pub rescue_delay: u32,
     pub immutable_hash: String,
+    /// Immutables to store in the escrow, must hash to `immutable_hash`.
+    /// When set, execute messages may omit their immutables.
+    pub immutables: Option<Immutables>,
+    /// Fee treasury that protocol fees are deposited into rather than transferred
+    #[serde(default)]
+    pub fee_treasury: Option<String>,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
+/// `immutables` can be omitted when they were stored at instantiation
 #[cw_serde]
 pub enum ExecuteMsg {
     Withdraw {
         secret: Binary,
-        immutables: Immutables,
+        immutables: Option<Immutables>,
     },
     PublicWithdraw {
         secret: Binary,
-        immutables: Immutables,
+        immutables: Option<Immutables>,
     },
     Cancel {
-        immutables: Immutables,
+        immutables: Option<Immutables>,
     },
     RescueFunds {
         token: String,
         amount: Uint256,
-        immutables: Immutables,
+        immutables: Option<Immutables>,
     },
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-     #[returns(Addr)]
+    #[returns(Addr)]
     QueryFactoryAddress {},
-     #[returns(Addr)]
+    #[returns(HexBinary)]
     QueryEscrowDstCodeHash {},
-}
\ No newline at end of file
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(BalanceResponse)]
+    Balance {},
+    #[returns(EscrowStatus)]
+    Status {},
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub safety_deposit_denom: String,
+    pub rescue_delay: u32,
+    pub immutable_hash: String,
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    pub balances: Vec<Coin>,
+    /// Balance of the escrowed token, CW20 included, when the immutables are stored
+    pub token: Option<Asset>,
+}
@@ -1,20 +1,176 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use shared::types::Immutables;
+use cosmwasm_std::{Addr, Binary, Uint256};
+use cw20::Cw20ReceiveMsg;
+use shared::types::{Immutables, TimelockWindows};
+use shared::{FeeCaps, MerkleFill, Order};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub escrow_dst_code_id: u64,
+    pub escrow_src_code_id: u64,
     pub safety_deposit_token: String,
+    /// Minimum timelock window lengths enforced on new escrows, none by default
+    pub min_timelock_windows: Option<TimelockWindows>,
+    /// Owner allowed to update the config, the instantiator by default
+    pub owner: Option<String>,
+    /// Rescue delay in seconds passed to new escrows, 86400 by default
+    pub rescue_delay: Option<u32>,
+    /// Caps on destination escrow fees, uncapped by default
+    pub fee_caps: Option<FeeCaps>,
+    /// Fee treasury that must receive all protocol fees, any recipient by default
+    pub fee_treasury: Option<String>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    CreateEscrowDst {
+        immutables: Box<Immutables>,
+        src_cancellation_timestamp: u64,
+        /// 1inch packed `Timelocks` uint256 as found in EVM orders,
+        /// replaces `immutables.timelocks` when set
+        packed_timelocks: Option<Uint256>,
+    },
+    /// Called by the resolver with a maker-signed order to lock the maker's
+    /// deposited funds in a new source escrow
+    CreateEscrowSrc {
+        immutables: Box<Immutables>,
+        order: Box<Order>,
+        /// ADR-36 secp256k1 signature of the order hash by the maker
+        signature: Binary,
+        /// Compressed secp256k1 public key of the maker
+        maker_pubkey: Binary,
+        /// Merkle proof of `immutables.hashlock`, required when the order allows multiple fills
+        merkle_fill: Option<MerkleFill>,
+    },
+    /// Deposit funds that makers' signed orders can later pull into source escrows,
+    /// or that pay the safety deposit of CW20 funded destination escrows
+    Deposit {},
+    /// Withdraw funds that are not locked in an escrow yet
+    WithdrawDeposit {
+        denom: String,
+        amount: Uint256,
+    },
+    /// CW20 hook, `msg` is a `ReceiveMsg`
+    Receive(Cw20ReceiveMsg),
+    /// Owner only, unset fields are left unchanged. Applies to escrows created afterwards.
+    UpdateConfig {
+        escrow_dst_code_id: Option<u64>,
+        escrow_src_code_id: Option<u64>,
+        safety_deposit_token: Option<String>,
+        rescue_delay: Option<u32>,
+        min_timelock_windows: Option<TimelockWindows>,
+        fee_caps: Option<FeeCaps>,
+        fee_treasury: Option<String>,
+        /// Removes the fee treasury, cannot be combined with `fee_treasury`
+        #[serde(default)]
+        clear_fee_treasury: bool,
+    },
+    /// Owner only, proposes `new_owner` who then has to accept
+    TransferOwnership { new_owner: String },
+    /// Called by the proposed owner to complete the transfer
+    AcceptOwnership {},
+    /// Owner only, migrates registered escrows (the factory is their admin)
+    /// to `new_code_id` with `msg` as their `MigrateMsg`
+    MigrateEscrows {
+        immutables_hashes: Vec<String>,
+        new_code_id: u64,
+        msg: Binary,
+    },
+}
+
+#[cw_serde]
+pub struct MigrateMsg {
+    /// Owner to set when migrating a factory deployed before ownership existed
+    pub owner: Option<String>,
+    /// Source escrow code id to set when migrating a factory deployed before source escrows existed
+    pub escrow_src_code_id: Option<u64>,
+}
+
+/// Messages sent along with CW20 tokens
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Creates a destination escrow funded with the sent CW20 tokens.
+    /// The native safety deposit is taken from the resolver's `Deposit`.
     CreateEscrowDst {
         immutables: Immutables,
         src_cancellation_timestamp: u64,
+        packed_timelocks: Option<Uint256>,
     },
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
-pub enum QueryMsg {}
\ No newline at end of file
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Predicted address of the destination escrow for `immutables`.
+    /// `deployed_at` overrides `immutables.timelocks.deployed_at` when set.
+    #[returns(Addr)]
+    AddressOfEscrowDst {
+        immutables: Immutables,
+        deployed_at: Option<u64>,
+    },
+    /// Predicted address of the source escrow for `immutables`.
+    /// `deployed_at` overrides `immutables.timelocks.deployed_at` when set.
+    #[returns(Addr)]
+    AddressOfEscrowSrc {
+        immutables: Immutables,
+        deployed_at: Option<u64>,
+    },
+    /// Escrows created by this factory, ordered by immutables hash
+    #[returns(EscrowsResponse)]
+    Escrows {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Escrow locked by `hashlock`, if any
+    #[returns(Option<EscrowInfo>)]
+    EscrowByHashlock { hashlock: String },
+    /// Escrows with `taker` as taker, ordered by immutables hash
+    #[returns(EscrowsResponse)]
+    EscrowsByTaker {
+        taker: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+    pub escrow_dst_code_id: u64,
+    pub escrow_src_code_id: u64,
+    pub safety_deposit_token: String,
+    pub rescue_delay: u32,
+    pub min_timelock_windows: TimelockWindows,
+    pub fee_caps: FeeCaps,
+    pub fee_treasury: Option<Addr>,
+}
+
+#[cw_serde]
+pub enum EscrowKind {
+    Src,
+    Dst,
+}
+
+/// Escrow created by the factory, keyed by its immutables hash
+#[cw_serde]
+pub struct EscrowInfo {
+    pub immutables_hash: String,
+    pub kind: EscrowKind,
+    pub address: Addr,
+    pub order_hash: String,
+    pub hashlock: String,
+    pub maker: String,
+    pub taker: String,
+    pub token: String,
+    pub amount: Uint256,
+    pub safety_deposit: Uint256,
+    pub deployed_at: u64,
+}
+
+#[cw_serde]
+pub struct EscrowsResponse {
+    pub escrows: Vec<EscrowInfo>,
+}
@@ -0,0 +1,62 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary, HexBinary, Uint256};
+use shared::{EscrowStatus, Immutables};
+
+pub use crate::escrow_dst::{BalanceResponse, ConfigResponse};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub safety_deposit_denom: String,
+    pub rescue_delay: u32,
+    pub immutable_hash: String,
+    /// Immutables to store in the escrow, must hash to `immutable_hash`.
+    /// When set, execute messages may omit their immutables.
+    pub immutables: Option<Immutables>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+/// `immutables` can be omitted when they were stored at instantiation
+#[cw_serde]
+pub enum ExecuteMsg {
+    Withdraw {
+        secret: Binary,
+        immutables: Option<Immutables>,
+    },
+    WithdrawTo {
+        secret: Binary,
+        target: String,
+        immutables: Option<Immutables>,
+    },
+    PublicWithdraw {
+        secret: Binary,
+        immutables: Option<Immutables>,
+    },
+    Cancel {
+        immutables: Option<Immutables>,
+    },
+    PublicCancel {
+        immutables: Option<Immutables>,
+    },
+    RescueFunds {
+        token: String,
+        amount: Uint256,
+        immutables: Option<Immutables>,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(Addr)]
+    QueryFactoryAddress {},
+    #[returns(HexBinary)]
+    QueryEscrowSrcCodeHash {},
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(BalanceResponse)]
+    Balance {},
+    #[returns(EscrowStatus)]
+    Status {},
+}
@@ -0,0 +1,67 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Binary};
+use shared::{EscrowStatus, FeeCaps, Immutables, TimelockWindows};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub safety_deposit_denom: String,
+    pub rescue_delay: u32,
+    pub min_timelock_windows: Option<TimelockWindows>,
+    /// Caps on escrow fees, uncapped by default
+    pub fee_caps: Option<FeeCaps>,
+    /// Fee treasury that must receive all protocol fees, any recipient by default
+    pub fee_treasury: Option<String>,
+}
+
+/// Escrows are addressed by the hash of their immutables, as returned in the
+/// `immutables_hash` attribute of `CreateEscrow`
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Lock the tokens and safety deposit sent with the message for a
+    /// destination escrow
+    CreateEscrow {
+        immutables: Box<Immutables>,
+        src_cancellation_timestamp: u64,
+    },
+    Withdraw {
+        immutables_hash: String,
+        secret: Binary,
+    },
+    PublicWithdraw {
+        immutables_hash: String,
+        secret: Binary,
+    },
+    Cancel {
+        immutables_hash: String,
+    },
+    /// Return the locked tokens and safety deposit of a still active escrow
+    /// to the taker once the rescue delay has passed
+    RescueFunds {
+        immutables_hash: String,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    #[returns(EscrowRecord)]
+    Escrow { immutables_hash: String },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub safety_deposit_denom: String,
+    pub rescue_delay: u32,
+    pub min_timelock_windows: TimelockWindows,
+    pub fee_caps: FeeCaps,
+    pub fee_treasury: Option<Addr>,
+}
+
+/// A single escrow held by the vault
+#[cw_serde]
+pub struct EscrowRecord {
+    pub immutables: Immutables,
+    pub status: EscrowStatus,
+}
@@ -0,0 +1,114 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Uint128};
+use cw20::Cw20ReceiveMsg;
+use shared::{Asset, AssetInfo};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Owner allowed to withdraw, usually a multisig, the instantiator by default
+    pub owner: Option<String>,
+    /// Factory whose escrows may deposit fees
+    pub factory: Option<String>,
+    /// Other contracts allowed to deposit fees, e.g. an escrow vault
+    #[serde(default)]
+    pub depositors: Vec<String>,
+    /// CW20 tokens accepted as fees
+    #[serde(default)]
+    pub cw20_tokens: Vec<String>,
+}
+
+/// Escrows pay protocol fees with `Deposit` or a CW20 `Send`, which the treasury
+/// records as accrued. Only escrows created by the factory and the configured
+/// depositors can deposit, and only in native or allowlisted CW20 tokens.
+/// Plain transfers are not counted as fees.
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Records the native funds sent with the message as accrued fees
+    Deposit {},
+    /// CW20 hook, `msg` is a `ReceiveMsg`
+    Receive(Cw20ReceiveMsg),
+    /// Owner only, sends `asset` out of the accrued fees not withdrawn yet to `recipient`
+    Withdraw { asset: Asset, recipient: String },
+    /// Owner only, unset fields are left unchanged, lists are replaced
+    UpdateConfig {
+        factory: Option<String>,
+        /// Removes the factory, cannot be combined with `factory`
+        #[serde(default)]
+        clear_factory: bool,
+        depositors: Option<Vec<String>>,
+        cw20_tokens: Option<Vec<String>>,
+    },
+    /// Owner only, proposes `new_owner` who then has to accept
+    TransferOwnership { new_owner: String },
+    /// Called by the proposed owner to complete the transfer
+    AcceptOwnership {},
+}
+
+/// Messages sent along with CW20 tokens
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Records the sent tokens as accrued fees
+    Deposit {},
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Fees accrued in `asset`
+    #[returns(AccruedResponse)]
+    Accrued { asset: AssetInfo },
+    /// Fees accrued in every asset deposited so far, ordered by denom or CW20 address
+    #[returns(AllAccruedResponse)]
+    AllAccrued {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Withdrawals ordered by id
+    #[returns(WithdrawalsResponse)]
+    Withdrawals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+    pub factory: Option<Addr>,
+    pub depositors: Vec<Addr>,
+    pub cw20_tokens: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct AccruedResponse {
+    pub asset: AssetInfo,
+    /// Deposited in total
+    pub accrued: Uint128,
+    /// Withdrawn by the owner
+    pub withdrawn: Uint128,
+    /// Left to withdraw, `accrued - withdrawn`
+    pub available: Uint128,
+}
+
+#[cw_serde]
+pub struct AllAccruedResponse {
+    pub accrued: Vec<AccruedResponse>,
+}
+
+/// A withdrawal made by the owner
+#[cw_serde]
+pub struct Withdrawal {
+    pub id: u64,
+    pub asset: Asset,
+    pub recipient: Addr,
+    pub height: u64,
+    pub timestamp: u64,
+}
+
+#[cw_serde]
+pub struct WithdrawalsResponse {
+    pub withdrawals: Vec<Withdrawal>,
+}
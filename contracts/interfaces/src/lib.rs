@@ -1,2 +1,5 @@
 pub mod escrow_factory;
 pub mod escrow_dst;
+pub mod escrow_src;
+pub mod escrow_vault;
+pub mod fee_treasury;
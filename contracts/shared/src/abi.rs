@@ -0,0 +1,159 @@
+use bech32::FromBase32;
+use cosmwasm_std::{Addr, Api, CanonicalAddr, StdError, StdResult, Uint256};
+use sha3::{Digest, Keccak256};
+
+use crate::{Immutables, Timelocks};
+
+/// A single 32 byte Solidity ABI word
+pub type Word = [u8; 32];
+
+/// Encode a hex string (optionally `0x` prefixed) of at most 32 bytes as a
+/// left-padded `bytes32`/`uint256` word
+pub fn hex_to_word(value: &str) -> StdResult<Word> {
+    let bytes = hex::decode(value.trim_start_matches("0x"))
+        .map_err(|_| StdError::generic_err(format!("Invalid hex value: {}", value)))?;
+    bytes_to_word(&bytes)
+}
+
+/// Left-pad up to 32 bytes into a word
+pub fn bytes_to_word(bytes: &[u8]) -> StdResult<Word> {
+    if bytes.len() > 32 {
+        return Err(StdError::generic_err("Value does not fit into 32 bytes"));
+    }
+    let mut word = [0u8; 32];
+    word[32 - bytes.len()..].copy_from_slice(bytes);
+    Ok(word)
+}
+
+/// Encode an address as `uint256`, like 1inch's `Address` type.
+/// `0x` hex addresses are used as is, bech32 addresses contribute their raw
+/// address bytes and anything else (native denoms) maps to its keccak256.
+pub fn address_to_word(value: &str) -> StdResult<Word> {
+    if value.starts_with("0x") {
+        return hex_to_word(value);
+    }
+
+    if let Ok((_, data, _)) = bech32::decode(value) {
+        if let Ok(bytes) = Vec::<u8>::from_base32(&data) {
+            if bytes.len() <= 32 {
+                return bytes_to_word(&bytes);
+            }
+        }
+    }
+
+    Ok(Keccak256::digest(value.as_bytes()).into())
+}
+
+/// Decode an `Address` word into a chain address, the inverse of
+/// `address_to_word` for bech32 addresses: 20 byte account addresses when the
+/// upper 12 bytes are zero, 32 byte contract addresses otherwise
+pub fn word_to_address(api: &dyn Api, word: &Word) -> StdResult<Addr> {
+    let bytes = if word[..12].iter().all(|byte| *byte == 0) {
+        &word[12..]
+    } else {
+        &word[..]
+    };
+    api.addr_humanize(&CanonicalAddr::from(bytes))
+}
+
+/// Encode a `uint256`
+pub fn uint256_to_word(value: Uint256) -> Word {
+    value.to_be_bytes()
+}
+
+/// Encode timelocks as the packed 1inch `Timelocks` word
+pub fn encode_timelocks(timelocks: &Timelocks) -> StdResult<Word> {
+    Ok(uint256_to_word(timelocks.to_packed_u256()?))
+}
+
+/// `abi.encode(immutables)` with the dynamic `parameters` replaced by their
+/// keccak256, i.e. the exact preimage hashed by 1inch's `ImmutablesLib.hash`.
+/// An explicit rescue delay is appended as a tenth `uint256` word.
+pub fn encode_immutables(immutables: &Immutables) -> StdResult<Vec<u8>> {
+    let words = [
+        hex_to_word(&immutables.order_hash)?,
+        hex_to_word(&immutables.hashlock)?,
+        address_to_word(&immutables.maker)?,
+        address_to_word(&immutables.taker)?,
+        address_to_word(&immutables.token)?,
+        uint256_to_word(immutables.amount),
+        uint256_to_word(immutables.safety_deposit),
+        encode_timelocks(&immutables.timelocks)?,
+        Keccak256::digest(&immutables.parameters).into(),
+    ];
+
+    let mut encoded = words.concat();
+    if let Some(rescue_delay) = immutables.rescue_delay {
+        encoded.extend_from_slice(&uint256_to_word(Uint256::from(rescue_delay)));
+    }
+
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccak256;
+
+    // Expected values computed independently with a reference keccak256 and
+    // Solidity `abi.encode` layout of 1inch `IBaseEscrow.Immutables`
+    fn immutables(maker: &str, token: &str, parameters: &[u8]) -> Immutables {
+        Immutables {
+            order_hash: keccak256(b"order"),
+            hashlock: format!("0x{}", keccak256(b"secret")),
+            maker: maker.to_string(),
+            taker: format!("0x{}", "22".repeat(20)),
+            token: token.to_string(),
+            amount: Uint256::from(1_000_000u128),
+            safety_deposit: Uint256::from(50_000u128),
+            timelocks: Timelocks {
+                deployed_at: 1_700_000_000,
+                src_withdrawal: 10,
+                src_public_withdrawal: 120,
+                src_cancellation: 121,
+                src_public_cancellation: 122,
+                dst_withdrawal: 10,
+                dst_public_withdrawal: 100,
+                dst_cancellation: 101,
+            },
+            parameters: parameters.to_vec(),
+            rescue_delay: None,
+        }
+    }
+
+    #[test]
+    fn test_encode_timelocks() {
+        let immutables = immutables("", "", b"");
+        assert_eq!(
+            hex::encode(encode_timelocks(&immutables.timelocks).unwrap()),
+            "6553f10000000065000000640000000a0000007a00000079000000780000000a"
+        );
+    }
+
+    #[test]
+    fn test_immutables_hash_evm_addresses() {
+        let immutables = immutables(
+            &format!("0x{}", "11".repeat(20)),
+            &format!("0x{}", "33".repeat(20)),
+            b"",
+        );
+        assert_eq!(
+            immutables.compute_immutables_hash().unwrap(),
+            "69105e5200d04caf31b77c2d33d172f55c6f6a6d019a0b082419fd56e602bd2f"
+        );
+    }
+
+    #[test]
+    fn test_immutables_hash_cosmos_addresses() {
+        // bech32 of the bytes 0x01..=0x14, native denom token and non-empty parameters
+        let immutables = immutables("osmo1qypqxpq9qcrsszg2pvxq6rs0zqg3yyc5helwsw", "uosmo", b"fees");
+        assert_eq!(
+            address_to_word(&immutables.maker).unwrap()[12..],
+            (1u8..=20).collect::<Vec<u8>>()[..]
+        );
+        assert_eq!(
+            immutables.compute_immutables_hash().unwrap(),
+            "b5c126e559839ee75529871ea6e698ef1004a37d5d2399a3bb53cff179dd304c"
+        );
+    }
+}
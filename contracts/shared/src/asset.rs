@@ -0,0 +1,201 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary, Addr, Api, BankMsg, Binary, Coin, CosmosMsg, QuerierWrapper, StdResult, Uint128,
+    Uint256, WasmMsg,
+};
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg};
+
+use crate::{is_cw20_token, ContractError};
+
+/// Kind of token held by an escrow
+#[cw_serde]
+pub enum AssetInfo {
+    /// Bank denom: native (`uosmo`), IBC (`ibc/...`) or TokenFactory (`factory/...`)
+    Native { denom: String },
+    /// CW20 token contract
+    Cw20 { contract_addr: Addr },
+}
+
+impl AssetInfo {
+    pub fn native(denom: impl Into<String>) -> Self {
+        AssetInfo::Native {
+            denom: denom.into(),
+        }
+    }
+
+    /// Resolve an `Immutables.token` string: valid contract addresses are CW20
+    /// tokens, anything else is a bank denom
+    pub fn from_token(api: &dyn Api, token: &str) -> Self {
+        if is_cw20_token(api, token) {
+            AssetInfo::Cw20 {
+                contract_addr: Addr::unchecked(token),
+            }
+        } else {
+            AssetInfo::native(token)
+        }
+    }
+
+    /// Balance of `address` in this asset
+    pub fn query_balance(
+        &self,
+        querier: &QuerierWrapper,
+        address: impl Into<String>,
+    ) -> StdResult<Uint128> {
+        match self {
+            AssetInfo::Native { denom } => Ok(querier.query_balance(address, denom)?.amount),
+            AssetInfo::Cw20 { contract_addr } => {
+                let response: Cw20BalanceResponse = querier.query_wasm_smart(
+                    contract_addr,
+                    &Cw20QueryMsg::Balance {
+                        address: address.into(),
+                    },
+                )?;
+                Ok(response.balance)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for AssetInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AssetInfo::Native { denom } => write!(f, "{}", denom),
+            AssetInfo::Cw20 { contract_addr } => write!(f, "{}", contract_addr),
+        }
+    }
+}
+
+/// An amount of an asset
+#[cw_serde]
+pub struct Asset {
+    pub info: AssetInfo,
+    pub amount: Uint128,
+}
+
+impl Asset {
+    pub fn new(info: AssetInfo, amount: impl Into<Uint128>) -> Self {
+        Asset {
+            info,
+            amount: amount.into(),
+        }
+    }
+
+    /// Build an asset from an `Immutables` amount, which must fit into a `Uint128`
+    pub fn from_uint256(info: AssetInfo, amount: Uint256) -> Result<Self, ContractError> {
+        let amount = amount
+            .try_into()
+            .map_err(|_| ContractError::UintConversionFailed {})?;
+        Ok(Asset { info, amount })
+    }
+
+    /// Message sending this asset from the contract to `recipient`
+    pub fn transfer_msg(&self, recipient: impl Into<String>) -> StdResult<CosmosMsg> {
+        match &self.info {
+            AssetInfo::Native { denom } => Ok(CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.into(),
+                amount: vec![Coin {
+                    denom: denom.clone(),
+                    amount: self.amount,
+                }],
+            })),
+            AssetInfo::Cw20 { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.into(),
+                    amount: self.amount,
+                })?,
+                funds: vec![],
+            })),
+        }
+    }
+
+    /// Message sending this asset from the contract to `contract` along with `msg`,
+    /// as funds of the execute message or through CW20 `Send`
+    pub fn send_msg(&self, contract: impl Into<String>, msg: Binary) -> StdResult<CosmosMsg> {
+        match &self.info {
+            AssetInfo::Native { denom } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract.into(),
+                msg,
+                funds: vec![Coin {
+                    denom: denom.clone(),
+                    amount: self.amount,
+                }],
+            })),
+            AssetInfo::Cw20 { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Send {
+                    contract: contract.into(),
+                    amount: self.amount,
+                    msg,
+                })?,
+                funds: vec![],
+            })),
+        }
+    }
+
+    /// Check that exactly this native asset, and nothing else, was sent along
+    /// with the message in `funds`
+    pub fn validate_native_deposit(&self, funds: &[Coin]) -> Result<(), ContractError> {
+        let denom = match &self.info {
+            AssetInfo::Native { denom } => denom,
+            AssetInfo::Cw20 { .. } => {
+                return Err(ContractError::MissingRequiredToken {
+                    token: self.info.to_string(),
+                })
+            }
+        };
+
+        let actual = funds
+            .iter()
+            .filter(|coin| &coin.denom == denom)
+            .map(|coin| coin.amount)
+            .sum::<Uint128>();
+        if actual < self.amount {
+            return Err(ContractError::InsufficientTokenAmount {
+                token: denom.clone(),
+                expected: self.amount.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+        if actual > self.amount {
+            return Err(ContractError::UnexpectedTokenAmount {
+                token: denom.clone(),
+                expected: self.amount.to_string(),
+                actual: actual.to_string(),
+            });
+        }
+        if let Some(coin) = funds.iter().find(|coin| &coin.denom != denom) {
+            return Err(ContractError::UnexpectedTokenAmount {
+                token: coin.denom.clone(),
+                expected: "0".to_string(),
+                actual: coin.amount.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that a CW20 asset was received from `token_contract` in a `Receive` hook
+    pub fn validate_cw20_deposit(
+        &self,
+        token_contract: &Addr,
+        amount: Uint128,
+    ) -> Result<(), ContractError> {
+        match &self.info {
+            AssetInfo::Cw20 { contract_addr } if contract_addr == token_contract => {}
+            _ => {
+                return Err(ContractError::MissingRequiredToken {
+                    token: self.info.to_string(),
+                })
+            }
+        }
+
+        if amount < self.amount {
+            return Err(ContractError::InsufficientTokenAmount {
+                token: self.info.to_string(),
+                expected: self.amount.to_string(),
+                actual: amount.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
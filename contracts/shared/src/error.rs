@@ -44,4 +44,106 @@ pub enum ContractError {
     UintConversionFailed {},
     #[error("error fee parsing")]
     ErrorFeeParsing {},
+
+    #[error("Invalid signature")]
+    InvalidSignature {},
+
+    #[error("Invalid order hash")]
+    InvalidOrderHash {},
+
+    #[error("Immutables do not match the signed order")]
+    OrderMismatch {},
+
+    #[error("Order was signed for another chain or factory")]
+    InvalidOrderDomain {},
+
+    #[error("Order has expired")]
+    OrderExpired {},
+
+    #[error("Escrow already withdrawn")]
+    EscrowAlreadyWithdrawn {},
+
+    #[error("Escrow already cancelled")]
+    EscrowAlreadyCancelled {},
+
+    #[error("Invalid escrow status transition from {from} to {to}")]
+    InvalidStatusTransition { from: String, to: String },
+
+    #[error("Invalid timelocks: {later} is before {earlier}")]
+    InvalidTimelockOrder { earlier: String, later: String },
+
+    #[error("Timelock window from {from} to {to} is too short: expected at least {min}s, got {actual}s")]
+    TimelockWindowTooShort {
+        from: String,
+        to: String,
+        min: u32,
+        actual: u32,
+    },
+
+    #[error("Invalid merkle proof")]
+    InvalidMerkleProof {},
+
+    #[error("Merkle proof is required for multiple fills orders")]
+    MissingMerkleProof {},
+
+    #[error("Invalid secrets amount")]
+    InvalidSecretsAmount {},
+
+    #[error("Invalid partial fill")]
+    InvalidPartialFill {},
+
+    #[error("Immutables are not stored in the escrow and must be provided")]
+    MissingImmutables {},
+
+    #[error("Escrow already exists")]
+    EscrowAlreadyExists {},
+
+    #[error("Escrow already created for order {order_hash}, hashlock {hashlock} and fill {index}")]
+    DuplicateEscrow { order_hash: String, hashlock: String, index: u64 },
+
+    #[error("Escrow deployed to {actual} instead of predicted {expected}")]
+    EscrowAddressMismatch { expected: String, actual: String },
+
+    #[error("Cannot migrate contract {actual} to {expected}")]
+    InvalidMigrationContract { expected: String, actual: String },
+
+    #[error("Cannot migrate from version {previous} to older version {new}")]
+    MigrationDowngrade { previous: String, new: String },
+
+    #[error("Rescue delay {actual} outside of [{min}, {max}]")]
+    InvalidRescueDelay { min: u32, max: u32, actual: u32 },
+
+    #[error("{fee} exceeds the cap of {cap_bps} bps")]
+    FeeCapExceeded { fee: String, cap_bps: u16 },
+
+    #[error("Fees exceed the escrowed amount {amount}")]
+    FeesExceedAmount { amount: String },
+
+    #[error("Invalid fee recipient: {recipient}")]
+    InvalidFeeRecipient { recipient: String },
+
+    #[error("Too many fee legs: {count}, at most {max}")]
+    TooManyFeeLegs { count: u32, max: u32 },
+
+    #[error("Invalid fee cap {cap}: {value}, at most {max}")]
+    InvalidFeeCap { cap: String, value: u32, max: u32 },
+
+    #[error("Unexpected token amount for {token}: expected {expected}, got {actual}")]
+    UnexpectedTokenAmount {
+        token: String,
+        expected: String,
+        actual: String,
+    },
+
+    #[error("Invalid minimum timelock window {window}: {value}, must be between 1 and {max}")]
+    InvalidTimelockWindow { window: String, value: u32, max: u32 },
+
+    #[error("Rescue delay {rescue_delay} ends before {stage}")]
+    RescueDelayTooShort { rescue_delay: u32, stage: String },
+
+    #[error("{depositor} is not allowed to deposit fees")]
+    UnauthorizedDepositor { depositor: String },
+
+    #[error("CW20 token {token} is not accepted as fees")]
+    UnsupportedFeeToken { token: String },
 }
\ No newline at end of file
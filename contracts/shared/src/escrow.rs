@@ -0,0 +1,42 @@
+use cosmwasm_std::Storage;
+use cw_storage_plus::Item;
+
+use crate::{ContractError, EscrowStatus, Immutables};
+
+pub const IMMUTABLE_HASH: Item<String> = Item::new("immutable_hash");
+pub const STATUS: Item<EscrowStatus> = Item::new("status");
+pub const IMMUTABLES: Item<Immutables> = Item::new("immutables");
+/// Set on escrows migrated from 0.1.0, whose `IMMUTABLE_HASH` is the legacy bincode hash
+pub const LEGACY_IMMUTABLE_HASH: Item<bool> = Item::new("legacy_immutable_hash");
+
+/// Moves the escrow to `next`, rejecting double settlement
+pub fn update_status(storage: &mut dyn Storage, next: EscrowStatus) -> Result<(), ContractError> {
+    let status = STATUS.load(storage)?.transition(next)?;
+    STATUS.save(storage, &status)?;
+    Ok(())
+}
+
+/// Immutables of the escrow: the supplied ones checked against the stored
+/// hash, or the ones stored at instantiation
+pub fn resolve_immutables(
+    storage: &dyn Storage,
+    immutables: Option<Immutables>,
+) -> Result<Immutables, ContractError> {
+    let Some(immutables) = immutables else {
+        return IMMUTABLES
+            .may_load(storage)?
+            .ok_or(ContractError::MissingImmutables {});
+    };
+
+    let immutables_hash = if LEGACY_IMMUTABLE_HASH.may_load(storage)?.unwrap_or_default() {
+        immutables
+            .compute_legacy_immutables_hash()
+            .map_err(|_| ContractError::InvalidImmutables {})?
+    } else {
+        immutables.compute_immutables_hash()?
+    };
+    if IMMUTABLE_HASH.load(storage)? != immutables_hash {
+        return Err(ContractError::InvalidImmutables {});
+    }
+    Ok(immutables)
+}
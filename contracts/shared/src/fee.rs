@@ -0,0 +1,656 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    attr, from_json, to_json_binary, Addr, Api, Attribute, CosmosMsg, StdError, StdResult, Uint128,
+    Uint256,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    address_to_word, uint256_to_word, word_to_address, Asset, AssetInfo, ContractError, Immutables,
+    Word,
+};
+
+/// Latest version of the JSON fee parameters
+pub const FEE_PARAMETERS_VERSION: u32 = 3;
+
+/// Most fee legs any fee parameters may carry
+pub const MAX_FEE_LEGS: usize = 10;
+
+/// Default limit on fee legs enforced by the factory
+pub const DEFAULT_MAX_FEE_LEGS: u32 = 5;
+
+/// 100% in basis points
+pub const MAX_BPS: u16 = 10_000;
+
+/// Length of the ABI-encoded fee parameters, four words
+const ABI_FEE_PARAMETERS_LEN: usize = 4 * 32;
+
+/// Fee structure decoded from immutables parameters.
+/// Absolute and basis-point fees add up; the basis-point fees and the surplus
+/// fee are only available in the JSON form from version 2, fee legs from version 3.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default)]
+pub struct FeeInfo {
+    /// JSON form version, 0 or missing for the original unversioned form
+    #[serde(default)]
+    pub version: u32,
+    pub protocol_fee_amount: Uint128,
+    pub integrator_fee_amount: Uint128,
+    pub protocol_fee_recipient: String,
+    pub integrator_fee_recipient: String,
+    /// Protocol fee in basis points of the escrowed amount
+    #[serde(default)]
+    pub protocol_fee_bps: u16,
+    /// Integrator fee in basis points of the escrowed amount
+    #[serde(default)]
+    pub integrator_fee_bps: u16,
+    /// Share of the protocol fee, in basis points, paid to the integrator
+    #[serde(default)]
+    pub integrator_share_bps: u16,
+    /// Output the order was quoted at, anything above it is surplus
+    #[serde(default)]
+    pub estimated_amount: Uint128,
+    /// Protocol cut of the surplus in basis points
+    #[serde(default)]
+    pub surplus_fee_bps: u16,
+    /// Additional fee recipients, such as referrers or sub-integrators
+    #[serde(default)]
+    pub fee_legs: Vec<FeeLeg>,
+}
+
+/// Message a fee treasury records protocol fees with, sent with native funds
+/// or as the hook of a CW20 `Send`
+#[cw_serde]
+pub enum FeeTreasuryMsg {
+    Deposit {},
+}
+
+/// Fee paid to an additional recipient
+#[cw_serde]
+pub struct FeeLeg {
+    pub recipient: String,
+    pub share: FeeShare,
+}
+
+#[cw_serde]
+pub enum FeeShare {
+    /// Absolute amount of the escrowed token
+    Amount(Uint128),
+    /// Basis points of the escrowed amount
+    Bps(u16),
+}
+
+/// Fees charged on a withdrawal
+#[cw_serde]
+#[derive(Default)]
+pub struct FeeBreakdown {
+    /// Paid to the protocol fee recipient, without the surplus fee
+    pub protocol_fee: Uint128,
+    /// Paid to the integrator fee recipient, including its share of the protocol fee
+    pub integrator_fee: Uint128,
+    /// Paid to the protocol fee recipient
+    pub surplus_fee: Uint128,
+    /// Paid to the fee legs, in the order of `FeeInfo::fee_legs`
+    pub leg_fees: Vec<Uint128>,
+    /// Left for the recipient of the escrowed tokens
+    pub remaining: Uint128,
+}
+
+impl FeeBreakdown {
+    pub fn total_leg_fees(&self) -> StdResult<Uint128> {
+        let mut total = Uint128::zero();
+        for fee in &self.leg_fees {
+            total = total.checked_add(*fee)?;
+        }
+        Ok(total)
+    }
+
+    pub fn total_fees(&self) -> StdResult<Uint128> {
+        Ok(self
+            .protocol_fee
+            .checked_add(self.integrator_fee)?
+            .checked_add(self.surplus_fee)?
+            .checked_add(self.total_leg_fees()?)?)
+    }
+
+    /// Attributes reporting a withdrawal's remaining amount and fees
+    pub fn attributes(&self) -> StdResult<Vec<Attribute>> {
+        Ok(vec![
+            attr("amount", self.remaining.to_string()),
+            attr("integrator_fee", self.integrator_fee.to_string()),
+            attr("protocol_fee", self.protocol_fee.to_string()),
+            attr("surplus_fee", self.surplus_fee.to_string()),
+            attr("fee_legs", self.total_leg_fees()?.to_string()),
+        ])
+    }
+}
+
+/// Check the fees of a new escrow before its funds are locked: they must be
+/// well formed, within `caps` and, when a `fee_treasury` is configured, pay
+/// protocol fees into it
+pub fn check_escrow_fees(
+    api: &dyn Api,
+    immutables: &Immutables,
+    caps: &FeeCaps,
+    fee_treasury: Option<&Addr>,
+) -> Result<FeeBreakdown, ContractError> {
+    let fee_info = FeeInfo::from_parameters(api, &immutables.parameters)?;
+    let fees = fee_info.validate(api, immutables.amount)?;
+    caps.check(&fees, immutables.amount)?;
+    if let Some(fee_treasury) = fee_treasury {
+        fee_info.check_fee_treasury(&fees, fee_treasury)?;
+    }
+    Ok(fees)
+}
+
+/// Messages paying out a withdrawn escrow: the escrowed amount, less fees, to
+/// the maker and the safety deposit in `safety_deposit_denom` to `caller`
+pub fn withdrawal_msgs(
+    api: &dyn Api,
+    immutables: &Immutables,
+    fee_treasury: Option<&Addr>,
+    safety_deposit_denom: &str,
+    caller: &Addr,
+) -> Result<(Vec<CosmosMsg>, FeeBreakdown), ContractError> {
+    let fee_info = FeeInfo::from_parameters(api, &immutables.parameters)?;
+
+    let token = AssetInfo::from_token(api, &immutables.token);
+    let (mut messages, fees) =
+        fee_info.payout_msgs(&token, immutables.amount, &immutables.maker, fee_treasury)?;
+
+    if immutables.safety_deposit > Uint256::zero() {
+        let safety_deposit = AssetInfo::native(safety_deposit_denom);
+        messages.push(Asset::from_uint256(safety_deposit, immutables.safety_deposit)?.transfer_msg(caller)?);
+    }
+
+    Ok((messages, fees))
+}
+
+impl FeeInfo {
+    /// Decode immutables parameters, either the JSON form or the 1inch EscrowDst
+    /// `abi.encode(uint256 protocolFeeAmount, uint256 integratorFeeAmount,
+    /// Address protocolFeeRecipient, Address integratorFeeRecipient)` form.
+    /// Empty parameters, as for orders without fees, decode to zero fees.
+    pub fn from_parameters(api: &dyn Api, parameters: &[u8]) -> Result<Self, ContractError> {
+        if parameters.is_empty() {
+            Ok(FeeInfo::default())
+        } else if parameters.first() == Some(&b'{') {
+            Self::from_json_parameters(parameters)
+        } else {
+            Self::from_abi_parameters(api, parameters)
+        }
+    }
+
+    fn from_json_parameters(parameters: &[u8]) -> Result<Self, ContractError> {
+        let fee_info: FeeInfo =
+            from_json(parameters).map_err(|_| ContractError::ErrorFeeParsing {})?;
+        if fee_info.version > FEE_PARAMETERS_VERSION {
+            return Err(ContractError::ErrorFeeParsing {});
+        }
+        if fee_info.version < 2
+            && (fee_info.protocol_fee_bps != 0
+                || fee_info.integrator_fee_bps != 0
+                || fee_info.integrator_share_bps != 0
+                || fee_info.surplus_fee_bps != 0)
+        {
+            return Err(ContractError::ErrorFeeParsing {});
+        }
+        if (fee_info.version < 3 && !fee_info.fee_legs.is_empty())
+            || fee_info.fee_legs.len() > MAX_FEE_LEGS
+        {
+            return Err(ContractError::ErrorFeeParsing {});
+        }
+        let leg_bps = fee_info.fee_legs.iter().filter_map(|leg| match leg.share {
+            FeeShare::Bps(bps) => Some(bps),
+            FeeShare::Amount(_) => None,
+        });
+        for bps in [
+            fee_info.protocol_fee_bps,
+            fee_info.integrator_fee_bps,
+            fee_info.integrator_share_bps,
+            fee_info.surplus_fee_bps,
+        ]
+        .into_iter()
+        .chain(leg_bps)
+        {
+            if bps > MAX_BPS {
+                return Err(ContractError::ErrorFeeParsing {});
+            }
+        }
+
+        Ok(fee_info)
+    }
+
+    fn from_abi_parameters(api: &dyn Api, parameters: &[u8]) -> Result<Self, ContractError> {
+        if parameters.len() != ABI_FEE_PARAMETERS_LEN {
+            return Err(ContractError::ErrorFeeParsing {});
+        }
+        let words: Vec<Word> = parameters
+            .chunks_exact(32)
+            .map(|chunk| chunk.try_into().unwrap())
+            .collect();
+
+        let amount = |word: &Word| -> Result<Uint128, ContractError> {
+            Uint256::from_be_bytes(*word)
+                .try_into()
+                .map_err(|_| ContractError::UintConversionFailed {})
+        };
+        let recipient = |word: &Word| -> Result<String, ContractError> {
+            word_to_address(api, word)
+                .map(|address| address.to_string())
+                .map_err(|_| ContractError::ErrorFeeParsing {})
+        };
+
+        Ok(FeeInfo {
+            protocol_fee_amount: amount(&words[0])?,
+            integrator_fee_amount: amount(&words[1])?,
+            protocol_fee_recipient: recipient(&words[2])?,
+            integrator_fee_recipient: recipient(&words[3])?,
+            ..FeeInfo::default()
+        })
+    }
+
+    /// ABI encoding of the absolute fees, as decoded by 1inch EscrowDst
+    pub fn to_abi_parameters(&self) -> Result<Vec<u8>, ContractError> {
+        let words = [
+            uint256_to_word(self.protocol_fee_amount.into()),
+            uint256_to_word(self.integrator_fee_amount.into()),
+            address_to_word(&self.protocol_fee_recipient)?,
+            address_to_word(&self.integrator_fee_recipient)?,
+        ];
+        Ok(words.concat())
+    }
+
+    /// Fees charged on a withdrawal of `amount`
+    pub fn breakdown(&self, amount: Uint256) -> Result<FeeBreakdown, ContractError> {
+        let amount: Uint128 = amount
+            .try_into()
+            .map_err(|_| ContractError::UintConversionFailed {})?;
+        let bps_of = |value: Uint128, bps: u16| value.multiply_ratio(bps, MAX_BPS);
+
+        let protocol_fee = self
+            .protocol_fee_amount
+            .checked_add(bps_of(amount, self.protocol_fee_bps))
+            .map_err(|_| ContractError::InsufficientEscrowBalance {})?;
+        let integrator_share = bps_of(protocol_fee, self.integrator_share_bps);
+        let integrator_fee = self
+            .integrator_fee_amount
+            .checked_add(bps_of(amount, self.integrator_fee_bps))
+            .and_then(|fee| fee.checked_add(integrator_share))
+            .map_err(|_| ContractError::InsufficientEscrowBalance {})?;
+
+        let surplus_fee = if !self.estimated_amount.is_zero() && amount > self.estimated_amount {
+            bps_of(amount - self.estimated_amount, self.surplus_fee_bps)
+        } else {
+            Uint128::zero()
+        };
+
+        let leg_fees = self
+            .fee_legs
+            .iter()
+            .map(|leg| match leg.share {
+                FeeShare::Amount(fee) => fee,
+                FeeShare::Bps(bps) => bps_of(amount, bps),
+            })
+            .collect();
+
+        let mut breakdown = FeeBreakdown {
+            protocol_fee: protocol_fee - integrator_share,
+            integrator_fee,
+            surplus_fee,
+            leg_fees,
+            remaining: Uint128::zero(),
+        };
+        breakdown.remaining = breakdown
+            .total_fees()
+            .ok()
+            .and_then(|total_fees| amount.checked_sub(total_fees).ok())
+            .ok_or(ContractError::InsufficientEscrowBalance {})?;
+
+        Ok(breakdown)
+    }
+
+    /// Check, before funds are locked, that the fees on `amount` fit into it and
+    /// that the recipients of non-zero fees are valid addresses
+    pub fn validate(&self, api: &dyn Api, amount: Uint256) -> Result<FeeBreakdown, ContractError> {
+        let breakdown = self.breakdown(amount).map_err(|e| match e {
+            ContractError::InsufficientEscrowBalance {} => ContractError::FeesExceedAmount {
+                amount: amount.to_string(),
+            },
+            e => e,
+        })?;
+
+        let leg_recipients = self
+            .fee_legs
+            .iter()
+            .map(|leg| &leg.recipient)
+            .zip(breakdown.leg_fees.iter().copied());
+        for (recipient, fee) in [
+            (&self.integrator_fee_recipient, breakdown.integrator_fee),
+            (&self.protocol_fee_recipient, breakdown.protocol_fee + breakdown.surplus_fee),
+        ]
+        .into_iter()
+        .chain(leg_recipients)
+        {
+            if !fee.is_zero() && api.addr_validate(recipient).is_err() {
+                return Err(ContractError::InvalidFeeRecipient {
+                    recipient: recipient.clone(),
+                });
+            }
+        }
+
+        Ok(breakdown)
+    }
+
+    /// Check that non-zero protocol and surplus fees in `breakdown` go to `fee_treasury`
+    pub fn check_fee_treasury(
+        &self,
+        breakdown: &FeeBreakdown,
+        fee_treasury: &Addr,
+    ) -> Result<(), ContractError> {
+        let protocol_fee = breakdown
+            .protocol_fee
+            .checked_add(breakdown.surplus_fee)
+            .map_err(StdError::from)?;
+        if !protocol_fee.is_zero() && self.protocol_fee_recipient != fee_treasury.as_str() {
+            return Err(ContractError::InvalidFeeRecipient {
+                recipient: self.protocol_fee_recipient.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Split `amount` of `token` into the fee transfers and the transfer of
+    /// the remainder to `recipient`, returning the messages and the breakdown.
+    /// Protocol fees for `fee_treasury` are deposited so that it records them.
+    pub fn payout_msgs(
+        &self,
+        token: &AssetInfo,
+        amount: Uint256,
+        recipient: &str,
+        fee_treasury: Option<&Addr>,
+    ) -> Result<(Vec<CosmosMsg>, FeeBreakdown), ContractError> {
+        let breakdown = self.breakdown(amount)?;
+        let mut messages = vec![];
+
+        if breakdown.integrator_fee > Uint128::zero() {
+            let fee = Asset::new(token.clone(), breakdown.integrator_fee);
+            messages.push(fee.transfer_msg(&self.integrator_fee_recipient)?);
+        }
+
+        // cannot overflow, the total fees fit into the amount
+        let protocol_fee = breakdown.protocol_fee + breakdown.surplus_fee;
+        if protocol_fee > Uint128::zero() {
+            let fee = Asset::new(token.clone(), protocol_fee);
+            match fee_treasury {
+                Some(fee_treasury) if *fee_treasury == self.protocol_fee_recipient => {
+                    let deposit = to_json_binary(&FeeTreasuryMsg::Deposit {})?;
+                    messages.push(fee.send_msg(fee_treasury, deposit)?);
+                }
+                _ => messages.push(fee.transfer_msg(&self.protocol_fee_recipient)?),
+            }
+        }
+
+        for (leg, fee) in self.fee_legs.iter().zip(breakdown.leg_fees.iter()) {
+            if !fee.is_zero() {
+                messages.push(Asset::new(token.clone(), *fee).transfer_msg(&leg.recipient)?);
+            }
+        }
+
+        if breakdown.remaining > Uint128::zero() {
+            messages.push(Asset::new(token.clone(), breakdown.remaining).transfer_msg(recipient)?);
+        }
+
+        Ok((messages, breakdown))
+    }
+}
+
+/// Upper bounds on the fees of an escrow, in basis points of its amount.
+/// Fee legs count towards the integrator fee.
+#[cw_serde]
+pub struct FeeCaps {
+    pub max_protocol_fee_bps: u16,
+    pub max_integrator_fee_bps: u16,
+    pub max_surplus_fee_bps: u16,
+    #[serde(default = "default_max_fee_legs")]
+    pub max_fee_legs: u32,
+}
+
+fn default_max_fee_legs() -> u32 {
+    DEFAULT_MAX_FEE_LEGS
+}
+
+impl Default for FeeCaps {
+    fn default() -> Self {
+        FeeCaps {
+            max_protocol_fee_bps: MAX_BPS,
+            max_integrator_fee_bps: MAX_BPS,
+            max_surplus_fee_bps: MAX_BPS,
+            max_fee_legs: DEFAULT_MAX_FEE_LEGS,
+        }
+    }
+}
+
+impl FeeCaps {
+    /// Check that the caps are within what fee parameters can express
+    pub fn validate(&self) -> Result<(), ContractError> {
+        for (cap, value, max) in [
+            ("max_protocol_fee_bps", self.max_protocol_fee_bps as u32, MAX_BPS as u32),
+            ("max_integrator_fee_bps", self.max_integrator_fee_bps as u32, MAX_BPS as u32),
+            ("max_surplus_fee_bps", self.max_surplus_fee_bps as u32, MAX_BPS as u32),
+            ("max_fee_legs", self.max_fee_legs, MAX_FEE_LEGS as u32),
+        ] {
+            if value > max {
+                return Err(ContractError::InvalidFeeCap {
+                    cap: cap.to_string(),
+                    value,
+                    max,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check the number of fee legs and each fee of `breakdown` against its cap
+    /// relative to `amount`
+    pub fn check(&self, breakdown: &FeeBreakdown, amount: Uint256) -> Result<(), ContractError> {
+        if breakdown.leg_fees.len() > self.max_fee_legs as usize {
+            return Err(ContractError::TooManyFeeLegs {
+                count: breakdown.leg_fees.len() as u32,
+                max: self.max_fee_legs,
+            });
+        }
+
+        let amount: Uint128 = amount
+            .try_into()
+            .map_err(|_| ContractError::UintConversionFailed {})?;
+        let integrator_fee = breakdown
+            .integrator_fee
+            .checked_add(breakdown.total_leg_fees()?)
+            .map_err(StdError::from)?;
+        for (fee_name, fee, cap_bps) in [
+            ("protocol_fee", breakdown.protocol_fee, self.max_protocol_fee_bps),
+            ("integrator_fee", integrator_fee, self.max_integrator_fee_bps),
+            ("surplus_fee", breakdown.surplus_fee, self.max_surplus_fee_bps),
+        ] {
+            if fee.full_mul(MAX_BPS) > amount.full_mul(cap_bps) {
+                return Err(ContractError::FeeCapExceeded {
+                    fee: fee_name.to_string(),
+                    cap_bps,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Bech32Api;
+    use cosmwasm_std::testing::MockApi;
+
+    #[test]
+    fn test_abi_fee_parameters_roundtrip() {
+        let api = Bech32Api::default();
+        let fee_info = FeeInfo {
+            protocol_fee_amount: Uint128::new(100),
+            integrator_fee_amount: Uint128::new(25),
+            protocol_fee_recipient: api.addr_make("protocol").to_string(),
+            integrator_fee_recipient: api.addr_make("integrator").to_string(),
+            ..FeeInfo::default()
+        };
+
+        let parameters = fee_info.to_abi_parameters().unwrap();
+        assert_eq!(parameters.len(), ABI_FEE_PARAMETERS_LEN);
+        assert_eq!(FeeInfo::from_parameters(&api, &parameters).unwrap(), fee_info);
+    }
+
+    #[test]
+    fn test_empty_fee_parameters() {
+        let api = MockApi::default();
+        let fee_info = FeeInfo::from_parameters(&api, &[]).unwrap();
+        assert_eq!(fee_info, FeeInfo::default());
+
+        let breakdown = fee_info.validate(&api, Uint256::from(1_000u128)).unwrap();
+        assert_eq!(breakdown.remaining, Uint128::new(1_000));
+    }
+
+    #[test]
+    fn test_json_fee_parameters_versions() {
+        let api = MockApi::default();
+        let legacy = br#"{"protocol_fee_amount":"1","integrator_fee_amount":"2","protocol_fee_recipient":"a","integrator_fee_recipient":"b"}"#;
+        assert_eq!(
+            FeeInfo::from_parameters(&api, legacy).unwrap().integrator_fee_amount,
+            Uint128::new(2)
+        );
+
+        let unknown = br#"{"version":4,"protocol_fee_amount":"1","integrator_fee_amount":"2","protocol_fee_recipient":"a","integrator_fee_recipient":"b"}"#;
+        assert!(matches!(
+            FeeInfo::from_parameters(&api, unknown),
+            Err(ContractError::ErrorFeeParsing {})
+        ));
+    }
+
+    #[test]
+    fn test_fee_breakdown() {
+        let fee_info = FeeInfo {
+            version: 2,
+            protocol_fee_amount: Uint128::new(10),
+            protocol_fee_bps: 100,
+            integrator_fee_bps: 50,
+            integrator_share_bps: 1_000,
+            estimated_amount: Uint128::new(9_000),
+            surplus_fee_bps: 5_000,
+            ..FeeInfo::default()
+        };
+
+        // protocol 10 + 100, integrator 50 + 11 share, surplus half of 1000
+        let breakdown = fee_info.breakdown(Uint256::from(10_000u128)).unwrap();
+        assert_eq!(breakdown.protocol_fee, Uint128::new(99));
+        assert_eq!(breakdown.integrator_fee, Uint128::new(61));
+        assert_eq!(breakdown.surplus_fee, Uint128::new(500));
+        assert_eq!(breakdown.remaining, Uint128::new(9_340));
+
+        let caps = FeeCaps {
+            max_surplus_fee_bps: 400,
+            ..FeeCaps::default()
+        };
+        assert!(matches!(
+            caps.check(&breakdown, Uint256::from(10_000u128)),
+            Err(ContractError::FeeCapExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_protocol_fee_deposited_into_treasury() {
+        let api = MockApi::default();
+        let treasury = api.addr_make("treasury");
+        let fee_info = FeeInfo {
+            protocol_fee_amount: Uint128::new(10),
+            protocol_fee_recipient: treasury.to_string(),
+            ..FeeInfo::default()
+        };
+        let token = AssetInfo::Cw20 {
+            contract_addr: api.addr_make("token"),
+        };
+
+        let (messages, _) = fee_info
+            .payout_msgs(&token, Uint256::from(100u128), "maker", Some(&treasury))
+            .unwrap();
+        let CosmosMsg::Wasm(cosmwasm_std::WasmMsg::Execute { msg, .. }) = &messages[0] else {
+            panic!("expected a CW20 message");
+        };
+        assert_eq!(
+            from_json::<cw20::Cw20ExecuteMsg>(msg).unwrap(),
+            cw20::Cw20ExecuteMsg::Send {
+                contract: treasury.to_string(),
+                amount: Uint128::new(10),
+                msg: to_json_binary(&FeeTreasuryMsg::Deposit {}).unwrap(),
+            }
+        );
+
+        // other recipients are paid with plain transfers
+        let (messages, _) = fee_info
+            .payout_msgs(&token, Uint256::from(100u128), "maker", Some(&api.addr_make("other")))
+            .unwrap();
+        assert_eq!(
+            messages[0],
+            Asset::new(token.clone(), 10u128).transfer_msg(treasury.as_str()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_fee_legs() {
+        let api = MockApi::default();
+        let referrer = api.addr_make("referrer").to_string();
+        let parameters = format!(
+            r#"{{"version":3,"protocol_fee_amount":"0","integrator_fee_amount":"0","protocol_fee_recipient":"","integrator_fee_recipient":"","fee_legs":[{{"recipient":"{referrer}","share":{{"bps":200}}}},{{"recipient":"{referrer}","share":{{"amount":"50"}}}}]}}"#
+        );
+        let fee_info = FeeInfo::from_parameters(&api, parameters.as_bytes()).unwrap();
+
+        let breakdown = fee_info.validate(&api, Uint256::from(10_000u128)).unwrap();
+        assert_eq!(breakdown.leg_fees, vec![Uint128::new(200), Uint128::new(50)]);
+        assert_eq!(breakdown.remaining, Uint128::new(9_750));
+
+        let (messages, _) = fee_info
+            .payout_msgs(&AssetInfo::native("uatom"), Uint256::from(10_000u128), &referrer, None)
+            .unwrap();
+        assert_eq!(messages.len(), 3);
+
+        let caps = FeeCaps {
+            max_fee_legs: 1,
+            ..FeeCaps::default()
+        };
+        assert!(matches!(
+            caps.check(&breakdown, Uint256::from(10_000u128)),
+            Err(ContractError::TooManyFeeLegs { count: 2, max: 1 })
+        ));
+
+        // fee legs are a version 3 feature
+        let legacy = parameters.replace(r#""version":3"#, r#""version":2"#);
+        assert!(matches!(
+            FeeInfo::from_parameters(&api, legacy.as_bytes()),
+            Err(ContractError::ErrorFeeParsing {})
+        ));
+    }
+
+    #[test]
+    fn test_fee_caps_validate() {
+        assert!(FeeCaps::default().validate().is_ok());
+
+        let caps = FeeCaps {
+            max_fee_legs: MAX_FEE_LEGS as u32 + 1,
+            ..FeeCaps::default()
+        };
+        assert!(matches!(
+            caps.validate(),
+            Err(ContractError::InvalidFeeCap { value: 11, max: 10, .. })
+        ));
+
+        let caps = FeeCaps {
+            max_surplus_fee_bps: MAX_BPS + 1,
+            ..FeeCaps::default()
+        };
+        assert!(matches!(caps.validate(), Err(ContractError::InvalidFeeCap { .. })));
+    }
+}
@@ -1,7 +1,23 @@
 pub mod types;
 pub mod utils;
 pub mod error;
+pub mod order;
+pub mod abi;
+pub mod merkle;
+pub mod asset;
+pub mod fee;
+pub mod migrate;
+pub mod escrow;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
 
 pub use types::*;
 pub use utils::*;
 pub use error::*;
+pub use order::*;
+pub use abi::*;
+pub use merkle::*;
+pub use asset::*;
+pub use fee::*;
+pub use migrate::*;
+pub use escrow::*;
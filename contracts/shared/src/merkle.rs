@@ -0,0 +1,164 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{StdResult, Uint256};
+use sha3::{Digest, Keccak256};
+
+use crate::{hex_to_word, ContractError, Word};
+
+/// Bit offset of the parts amount in a multiple fills `hashlockInfo`
+pub const PARTS_AMOUNT_OFFSET: usize = 240;
+
+/// Proof that an escrow hashlock is the secret hash at `index` of the Merkle
+/// tree of secret hashes committed to by a multiple fills order.
+/// The escrow itself keeps the leaf secret hash as its hashlock, so the secret
+/// revealed for a fill is checked against that leaf as usual.
+#[cw_serde]
+pub struct MerkleFill {
+    /// Merkle root in the lower 240 bits and parts amount in the top 16 bits,
+    /// like 1inch `hashlockInfo`
+    pub hashlock_info: String,
+    pub index: u64,
+    pub proof: Vec<String>,
+}
+
+impl MerkleFill {
+    /// Verify that `secret_hash` is the leaf at `self.index` of the tree
+    pub fn verify(&self, secret_hash: &str) -> Result<(), ContractError> {
+        let leaf = merkle_leaf(self.index, secret_hash)?;
+        let proof = self
+            .proof
+            .iter()
+            .map(|node| hex_to_word(node))
+            .collect::<StdResult<Vec<Word>>>()?;
+        let root = process_proof(leaf, &proof);
+
+        if shorten_root(root) != shorten_root(hex_to_word(&self.hashlock_info)?) {
+            return Err(ContractError::InvalidMerkleProof {});
+        }
+        Ok(())
+    }
+
+    /// Number of parts the order is split into, `secrets - 1`
+    pub fn parts_amount(&self) -> StdResult<u64> {
+        let hashlock_info = hex_to_word(&self.hashlock_info)?;
+        Ok(u16::from_be_bytes([hashlock_info[0], hashlock_info[1]]) as u64)
+    }
+}
+
+/// Leaf of the secrets tree: `keccak256(abi.encodePacked(uint64(index), secretHash))`
+pub fn merkle_leaf(index: u64, secret_hash: &str) -> StdResult<Word> {
+    let mut hasher = Keccak256::new();
+    hasher.update(index.to_be_bytes());
+    hasher.update(hex_to_word(secret_hash)?);
+    Ok(hasher.finalize().into())
+}
+
+/// Rebuild the root from a leaf and its proof, hashing sorted pairs like
+/// OpenZeppelin `MerkleProof.processProof`
+pub fn process_proof(leaf: Word, proof: &[Word]) -> Word {
+    proof.iter().fold(leaf, |node, sibling| hash_pair(node, *sibling))
+}
+
+/// Commutative keccak256 of two nodes
+pub fn hash_pair(a: Word, b: Word) -> Word {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    let mut hasher = Keccak256::new();
+    hasher.update(first);
+    hasher.update(second);
+    hasher.finalize().into()
+}
+
+/// Drop the parts amount from a `hashlockInfo`, keeping the lower 240 bits
+fn shorten_root(mut root: Word) -> Word {
+    root[..(256 - PARTS_AMOUNT_OFFSET) / 8].fill(0);
+    root
+}
+
+/// Check that a fill of `making_amount` may use the secret at `validated_index - 1`,
+/// ported from 1inch `BaseEscrowFactory._isValidPartialFill`
+pub fn is_valid_partial_fill(
+    making_amount: Uint256,
+    remaining_making_amount: Uint256,
+    order_making_amount: Uint256,
+    parts_amount: u64,
+    validated_index: u64,
+) -> bool {
+    if making_amount.is_zero()
+        || making_amount > remaining_making_amount
+        || remaining_making_amount > order_making_amount
+    {
+        return false;
+    }
+
+    let parts_amount = Uint256::from(parts_amount);
+    let filled_amount = order_making_amount - remaining_making_amount;
+    let calculated_index =
+        (filled_amount + making_amount - Uint256::one()) * parts_amount / order_making_amount;
+
+    if remaining_making_amount == making_amount {
+        // If the order is filled to completion, a secret with index i + 1 must be used
+        return calculated_index + Uint256::from(2u8) == Uint256::from(validated_index);
+    } else if order_making_amount != remaining_making_amount {
+        // Calculate the previous fill index only if this is not the first fill
+        let prev_calculated_index =
+            (filled_amount - Uint256::one()) * parts_amount / order_making_amount;
+        if calculated_index == prev_calculated_index {
+            return false;
+        }
+    }
+
+    calculated_index + Uint256::one() == Uint256::from(validated_index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccak256;
+
+    fn tree(secret_hashes: &[String]) -> (Vec<Word>, Word) {
+        let leaves: Vec<Word> = secret_hashes
+            .iter()
+            .enumerate()
+            .map(|(index, secret_hash)| merkle_leaf(index as u64, secret_hash).unwrap())
+            .collect();
+        let root = hash_pair(hash_pair(leaves[0], leaves[1]), hash_pair(leaves[2], leaves[3]));
+        (leaves, root)
+    }
+
+    #[test]
+    fn test_merkle_fill_verify() {
+        let secret_hashes: Vec<String> = (0u8..4).map(|i| keccak256(&[i])).collect();
+        let (leaves, root) = tree(&secret_hashes);
+
+        // parts amount = 3 in the top 16 bits
+        let mut hashlock_info = root;
+        hashlock_info[0] = 0;
+        hashlock_info[1] = 3;
+
+        let fill = MerkleFill {
+            hashlock_info: hex::encode(hashlock_info),
+            index: 2,
+            proof: vec![
+                hex::encode(leaves[3]),
+                hex::encode(hash_pair(leaves[0], leaves[1])),
+            ],
+        };
+        assert_eq!(fill.parts_amount().unwrap(), 3);
+        assert!(fill.verify(&secret_hashes[2]).is_ok());
+        assert!(matches!(
+            fill.verify(&secret_hashes[1]),
+            Err(ContractError::InvalidMerkleProof {})
+        ));
+    }
+
+    #[test]
+    fn test_is_valid_partial_fill() {
+        let total = Uint256::from(100u8);
+        // first fill of 30 with 4 parts uses secret 1
+        assert!(is_valid_partial_fill(Uint256::from(30u8), total, total, 4, 2));
+        assert!(!is_valid_partial_fill(Uint256::from(30u8), total, total, 4, 1));
+        // filling the rest uses the extra secret
+        assert!(is_valid_partial_fill(Uint256::from(70u8), Uint256::from(70u8), total, 4, 5));
+        // a fill staying inside the previous part is rejected
+        assert!(!is_valid_partial_fill(Uint256::from(10u8), Uint256::from(70u8), total, 4, 2));
+    }
+}
@@ -0,0 +1,36 @@
+use cosmwasm_std::{StdError, Storage};
+use cw2::get_contract_version;
+use semver::Version;
+
+use crate::ContractError;
+
+/// Check that the stored cw2 info belongs to `contract_name` and is not newer
+/// than `new_version`, returning the version being migrated from
+pub fn check_migration(
+    storage: &dyn Storage,
+    contract_name: &str,
+    new_version: &str,
+) -> Result<Version, ContractError> {
+    let stored = get_contract_version(storage)?;
+    if stored.contract != contract_name {
+        return Err(ContractError::InvalidMigrationContract {
+            expected: contract_name.to_string(),
+            actual: stored.contract,
+        });
+    }
+
+    let previous = parse_version(&stored.version)?;
+    if previous > parse_version(new_version)? {
+        return Err(ContractError::MigrationDowngrade {
+            previous: stored.version,
+            new: new_version.to_string(),
+        });
+    }
+
+    Ok(previous)
+}
+
+fn parse_version(version: &str) -> Result<Version, ContractError> {
+    Version::parse(version)
+        .map_err(|e| StdError::generic_err(format!("Invalid contract version {}: {}", version, e)).into())
+}
@@ -0,0 +1,131 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_json_vec, Addr, Api, Binary, StdResult, Uint256};
+use ripemd::Ripemd160;
+use sha2::{Digest as Sha2Digest, Sha256};
+
+use crate::{keccak256, ContractError, Immutables, Timelocks};
+
+/// Maker-signed cross-chain order originating on Cosmos
+#[cw_serde]
+pub struct Order {
+    pub salt: Uint256,
+    pub maker: String,
+    /// Address receiving the taker asset on the destination chain
+    pub receiver: String,
+    pub maker_asset: String,
+    pub taker_asset: String,
+    pub making_amount: Uint256,
+    pub taking_amount: Uint256,
+    /// Secret hash, or the `hashlockInfo` Merkle root of secret hashes when
+    /// `allow_multiple_fills` is set
+    pub hashlock: String,
+    /// Allow the order to be filled in parts, each part locked by its own secret
+    #[serde(default)]
+    pub allow_multiple_fills: bool,
+    pub src_safety_deposit: Uint256,
+    pub dst_safety_deposit: Uint256,
+    pub timelocks: Timelocks,
+    pub dst_chain_id: String,
+    /// Chain id of the source chain, so the signature cannot be replayed on another chain
+    pub src_chain_id: String,
+    /// Factory allowed to fill the order, so the signature cannot be replayed on another factory
+    pub factory: String,
+    /// Unix timestamp in seconds after which the order can no longer be filled, 0 for no expiry
+    pub expiration: u64,
+    /// Rescue delay of the source escrow, the factory's when unset
+    #[serde(default)]
+    pub rescue_delay: Option<u32>,
+}
+
+impl Order {
+    /// Compute the order hash signed by the maker
+    pub fn compute_order_hash(&self) -> StdResult<String> {
+        let serialized = to_json_vec(self)?;
+        Ok(keccak256(&serialized))
+    }
+
+    /// Check that the source escrow immutables were built from this order.
+    /// For multiple fills orders the fill amount and the hashlock leaf are
+    /// checked by the factory against the remaining amount and Merkle proof.
+    pub fn validate_immutables(&self, immutables: &Immutables) -> Result<(), ContractError> {
+        let mut order_timelocks = self.timelocks.clone();
+        order_timelocks.deployed_at = immutables.timelocks.deployed_at;
+
+        if immutables.order_hash != self.compute_order_hash()? {
+            return Err(ContractError::InvalidOrderHash {});
+        }
+
+        if immutables.maker != self.maker
+            || immutables.token != self.maker_asset
+            || (!self.allow_multiple_fills
+                && (immutables.hashlock != self.hashlock || immutables.amount != self.making_amount))
+            || immutables.safety_deposit != self.src_safety_deposit
+            || immutables.timelocks != order_timelocks
+            || immutables.rescue_delay != self.rescue_delay
+        {
+            return Err(ContractError::OrderMismatch {});
+        }
+
+        Ok(())
+    }
+
+    /// Check that the order is filled on the chain and by the factory it was signed for
+    pub fn validate_domain(&self, chain_id: &str, factory: &Addr) -> Result<(), ContractError> {
+        if self.src_chain_id != chain_id || self.factory != factory.as_str() {
+            return Err(ContractError::InvalidOrderDomain {});
+        }
+        Ok(())
+    }
+
+    pub fn is_expired(&self, current_time: u64) -> bool {
+        self.expiration != 0 && current_time >= self.expiration
+    }
+}
+
+/// Build the ADR-36 amino sign document for arbitrary `data` signed by `signer`
+/// (the document wallets such as Keplr produce for `signArbitrary`)
+pub fn adr36_sign_bytes(signer: &str, data: &[u8]) -> Vec<u8> {
+    format!(
+        r#"{{"account_number":"0","chain_id":"","fee":{{"amount":[],"gas":"0"}},"memo":"","msgs":[{{"type":"sign/MsgSignData","value":{{"data":"{}","signer":"{}"}}}}],"sequence":"0"}}"#,
+        Binary::from(data).to_base64(),
+        signer,
+    )
+    .into_bytes()
+}
+
+/// Verify an ADR-36 secp256k1 signature by `signer` over `data`.
+/// The public key must also derive the signer's address.
+pub fn verify_adr36_signature(
+    api: &dyn Api,
+    signer: &str,
+    data: &[u8],
+    signature: &Binary,
+    pubkey: &Binary,
+) -> Result<(), ContractError> {
+    let signer_canonical = api.addr_canonicalize(signer)?;
+    let pubkey_address = Ripemd160::digest(Sha256::digest(pubkey.as_slice()));
+    if signer_canonical.as_slice() != pubkey_address.as_slice() {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    let message_hash = Sha256::digest(adr36_sign_bytes(signer, data));
+    let verified = api
+        .secp256k1_verify(&message_hash, signature, pubkey)
+        .map_err(|_| ContractError::InvalidSignature {})?;
+    if !verified {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    Ok(())
+}
+
+/// Verify that the maker signed the hash of `order` with ADR-36
+pub fn verify_order_signature(
+    api: &dyn Api,
+    order: &Order,
+    signature: &Binary,
+    pubkey: &Binary,
+) -> Result<(), ContractError> {
+    let order_hash = order.compute_order_hash()?;
+    verify_adr36_signature(api, &order.maker, order_hash.as_bytes(), signature, pubkey)
+}
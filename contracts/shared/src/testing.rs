@@ -0,0 +1,144 @@
+use bech32::{FromBase32, ToBase32, Variant};
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    Addr, Api, CanonicalAddr, OwnedDeps, RecoverPubkeyError, Response, StdError, StdResult,
+    Uint256, VerificationError,
+};
+use std::marker::PhantomData;
+
+use crate::{Immutables, Timelocks};
+
+const BECH32_PREFIX: &str = "cosmwasm";
+
+/// `MockApi` with real bech32 addresses, so that canonical addresses of any
+/// length round-trip: 20 byte account and 32 byte `Instantiate2` addresses.
+/// `MockApi::addr_make` addresses are valid for it.
+#[derive(Copy, Clone, Default)]
+pub struct Bech32Api {
+    mock: MockApi,
+}
+
+impl Bech32Api {
+    pub fn addr_make(&self, input: &str) -> Addr {
+        self.mock.addr_make(input)
+    }
+}
+
+impl Api for Bech32Api {
+    fn addr_validate(&self, human: &str) -> StdResult<Addr> {
+        let canonical = self.addr_canonicalize(human)?;
+        let normalized = self.addr_humanize(&canonical)?;
+        if normalized != human {
+            return Err(StdError::generic_err("Invalid input: address not normalized"));
+        }
+        Ok(normalized)
+    }
+
+    fn addr_canonicalize(&self, human: &str) -> StdResult<CanonicalAddr> {
+        let (prefix, data, _) = bech32::decode(human)
+            .map_err(|e| StdError::generic_err(format!("Invalid input: {}", e)))?;
+        if prefix != BECH32_PREFIX {
+            return Err(StdError::generic_err("Invalid input: wrong bech32 prefix"));
+        }
+        let bytes = Vec::<u8>::from_base32(&data)
+            .map_err(|e| StdError::generic_err(format!("Invalid input: {}", e)))?;
+        Ok(bytes.into())
+    }
+
+    fn addr_humanize(&self, canonical: &CanonicalAddr) -> StdResult<Addr> {
+        bech32::encode(BECH32_PREFIX, canonical.as_slice().to_base32(), Variant::Bech32)
+            .map(Addr::unchecked)
+            .map_err(|e| StdError::generic_err(format!("Invalid input: {}", e)))
+    }
+
+    fn secp256k1_verify(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.mock.secp256k1_verify(message_hash, signature, public_key)
+    }
+
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Result<Vec<u8>, RecoverPubkeyError> {
+        self.mock.secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+    }
+
+    fn ed25519_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<bool, VerificationError> {
+        self.mock.ed25519_verify(message, signature, public_key)
+    }
+
+    fn ed25519_batch_verify(
+        &self,
+        messages: &[&[u8]],
+        signatures: &[&[u8]],
+        public_keys: &[&[u8]],
+    ) -> Result<bool, VerificationError> {
+        self.mock.ed25519_batch_verify(messages, signatures, public_keys)
+    }
+
+    fn debug(&self, message: &str) {
+        self.mock.debug(message)
+    }
+}
+
+/// `mock_dependencies` with a `Bech32Api`
+pub fn mock_bech32_dependencies() -> OwnedDeps<MockStorage, Bech32Api, MockQuerier> {
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: Bech32Api::default(),
+        querier: MockQuerier::default(),
+        custom_query_type: PhantomData,
+    }
+}
+
+/// Timelocks of the test escrows: each stage opens 10 seconds after the previous one
+pub fn mock_timelocks() -> Timelocks {
+    Timelocks {
+        deployed_at: 0,
+        src_withdrawal: 10,
+        src_public_withdrawal: 20,
+        src_cancellation: 30,
+        src_public_cancellation: 40,
+        dst_withdrawal: 10,
+        dst_public_withdrawal: 20,
+        dst_cancellation: 30,
+    }
+}
+
+/// Immutables of a fee-free `uatom` escrow from `maker` to `resolver`
+pub fn mock_immutables(hashlock: String) -> Immutables {
+    let api = MockApi::default();
+    Immutables {
+        order_hash: "11".repeat(32),
+        hashlock,
+        maker: api.addr_make("maker").to_string(),
+        taker: api.addr_make("resolver").to_string(),
+        token: "uatom".to_string(),
+        amount: Uint256::from(1_000u128),
+        safety_deposit: Uint256::from(100u128),
+        timelocks: mock_timelocks(),
+        parameters: br#"{"protocol_fee_amount":"0","integrator_fee_amount":"0","protocol_fee_recipient":"","integrator_fee_recipient":""}"#.to_vec(),
+        rescue_delay: None,
+    }
+}
+
+/// Value of the `key` attribute of `response`
+pub fn attribute(response: &Response, key: &str) -> String {
+    response
+        .attributes
+        .iter()
+        .find(|attribute| attribute.key == key)
+        .map(|attribute| attribute.value.clone())
+        .unwrap()
+}
@@ -1,8 +1,9 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{StdError, StdResult, Timestamp, Uint256};
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 
-use crate::keccak256;
+use crate::{encode_immutables, keccak256, ContractError};
 
 #[cw_serde]
 pub struct Immutables {
@@ -13,29 +14,62 @@ pub struct Immutables {
     pub token: String,
     pub amount: Uint256,
     pub safety_deposit: Uint256,
+    /// May be omitted when the factory receives the packed form separately
+    #[serde(default)]
     pub timelocks: Timelocks,
     pub parameters: Vec<u8>,
+    /// Rescue delay in seconds overriding the factory's. Hashed as an extra
+    /// trailing word, so leave unset for hashes matching EVM escrows.
+    #[serde(default)]
+    pub rescue_delay: Option<u32>,
 }
 
 impl Immutables {
-    /// Compute hash of immutables for deterministic address (EIP-712 style)
+    /// Compute hash of immutables for deterministic address, identical to
+    /// Solidity `keccak256(abi.encode(immutables))` as done by 1inch `ImmutablesLib.hash`
     pub fn compute_immutables_hash(&self) -> StdResult<String> {
-        let parameters_hash = keccak256(&self.parameters);
+        Ok(keccak256(&encode_immutables(self)?))
+    }
 
-        let mut modified_immutables = self.clone();
+    /// Hash of escrows created before 0.2.0: keccak256 of the bincode encoded
+    /// immutables with `parameters` replaced by their keccak256 hash
+    pub fn compute_legacy_immutables_hash(&self) -> StdResult<String> {
+        #[derive(Serialize)]
+        struct LegacyImmutables<'a> {
+            order_hash: &'a str,
+            hashlock: &'a str,
+            maker: &'a str,
+            taker: &'a str,
+            token: &'a str,
+            amount: Uint256,
+            safety_deposit: Uint256,
+            timelocks: &'a Timelocks,
+            parameters: Vec<u8>,
+        }
 
-        // Convert the hex string hash back to bytes (32 bytes for keccak256)
-        let parameters_hash_bytes = hex::decode(&parameters_hash)
-            .map_err(|_| StdError::generic_err("Failed to decode parameters hash"))?;
-        modified_immutables.parameters = parameters_hash_bytes;
+        if self.rescue_delay.is_some() {
+            return Err(StdError::generic_err("Legacy immutables have no rescue delay"));
+        }
 
-        let serialized = bincode::serialize(&modified_immutables)
+        let legacy = LegacyImmutables {
+            order_hash: &self.order_hash,
+            hashlock: &self.hashlock,
+            maker: &self.maker,
+            taker: &self.taker,
+            token: &self.token,
+            amount: self.amount,
+            safety_deposit: self.safety_deposit,
+            timelocks: &self.timelocks,
+            parameters: Keccak256::digest(&self.parameters).to_vec(),
+        };
+        let serialized = bincode::serialize(&legacy)
             .map_err(|_| StdError::serialize_err("Immutables", "Failed to serialize"))?;
         Ok(keccak256(&serialized))
     }
 }
 
 #[cw_serde]
+#[derive(Default)]
 pub struct Timelocks {
     pub deployed_at: u64,
     pub src_withdrawal: u32,
@@ -52,6 +86,107 @@ impl Timelocks {
         self.deployed_at = time.seconds()
     }
 
+    /// Pack into the 1inch `Timelocks` uint256: 32 bit stage offsets from the
+    /// lowest bits upwards in `TimelocksLib.Stage` order, `deployedAt` in the top 32 bits
+    pub fn to_packed_u256(&self) -> StdResult<Uint256> {
+        let deployed_at: u32 = self
+            .deployed_at
+            .try_into()
+            .map_err(|_| StdError::generic_err("deployed_at does not fit into 32 bits"))?;
+
+        let slots = [
+            self.src_withdrawal,
+            self.src_public_withdrawal,
+            self.src_cancellation,
+            self.src_public_cancellation,
+            self.dst_withdrawal,
+            self.dst_public_withdrawal,
+            self.dst_cancellation,
+            deployed_at,
+        ];
+
+        let mut packed = [0u8; 32];
+        for (index, value) in slots.iter().enumerate() {
+            let end = 32 - index * 4;
+            packed[end - 4..end].copy_from_slice(&value.to_be_bytes());
+        }
+        Ok(Uint256::from_be_bytes(packed))
+    }
+
+    /// Unpack a 1inch `Timelocks` uint256, the inverse of `to_packed_u256`
+    pub fn from_packed_u256(packed: Uint256) -> Self {
+        let packed = packed.to_be_bytes();
+        let slot = |index: usize| {
+            let end = 32 - index * 4;
+            u32::from_be_bytes(packed[end - 4..end].try_into().unwrap())
+        };
+
+        Timelocks {
+            deployed_at: slot(7) as u64,
+            src_withdrawal: slot(0),
+            src_public_withdrawal: slot(1),
+            src_cancellation: slot(2),
+            src_public_cancellation: slot(3),
+            dst_withdrawal: slot(4),
+            dst_public_withdrawal: slot(5),
+            dst_cancellation: slot(6),
+        }
+    }
+
+    /// Validate that the dst stages are ordered withdrawal <= public withdrawal
+    /// <= cancellation and that each window is at least as long as configured
+    pub fn validate_dst(&self, windows: &TimelockWindows) -> Result<(), ContractError> {
+        check_window(
+            ("dst_withdrawal", self.dst_withdrawal),
+            ("dst_public_withdrawal", self.dst_public_withdrawal),
+            windows.min_withdrawal,
+        )?;
+        check_window(
+            ("dst_public_withdrawal", self.dst_public_withdrawal),
+            ("dst_cancellation", self.dst_cancellation),
+            windows.min_public_withdrawal,
+        )
+    }
+
+    /// Validate that the src stages are ordered withdrawal <= public withdrawal
+    /// <= cancellation <= public cancellation and that each window is at least
+    /// as long as configured
+    pub fn validate_src(&self, windows: &TimelockWindows) -> Result<(), ContractError> {
+        check_window(
+            ("src_withdrawal", self.src_withdrawal),
+            ("src_public_withdrawal", self.src_public_withdrawal),
+            windows.min_withdrawal,
+        )?;
+        check_window(
+            ("src_public_withdrawal", self.src_public_withdrawal),
+            ("src_cancellation", self.src_cancellation),
+            windows.min_public_withdrawal,
+        )?;
+        check_window(
+            ("src_cancellation", self.src_cancellation),
+            ("src_public_cancellation", self.src_public_cancellation),
+            windows.min_cancellation,
+        )
+    }
+
+    /// Validate ordering and window lengths of both the src and dst stages
+    pub fn validate(&self, windows: &TimelockWindows) -> Result<(), ContractError> {
+        self.validate_src(windows)?;
+        self.validate_dst(windows)
+    }
+
+    /// Check that a rescue after `rescue_delay` cannot start before `stage`,
+    /// the last settlement stage of the escrow
+    pub fn validate_rescue_delay(&self, rescue_delay: u32, stage: TimelockStage) -> Result<(), ContractError> {
+        if self.get_timelock(TimelockStage::RescueDelay(rescue_delay)) < self.get_timelock(stage.clone()) {
+            return Err(ContractError::RescueDelayTooShort {
+                rescue_delay,
+                stage: format!("{:?}", stage),
+            });
+        }
+        Ok(())
+    }
+
     /// Get timelock value for given stage
     pub fn get_timelock(&self, stage: TimelockStage) -> u64 {
         let base_time = self.deployed_at;
@@ -74,6 +209,67 @@ impl Timelocks {
     }
 }
 
+/// Check that stage `later` is not before stage `earlier` and leaves at least `min` seconds between them
+fn check_window(earlier: (&str, u32), later: (&str, u32), min: u32) -> Result<(), ContractError> {
+    if later.1 < earlier.1 {
+        return Err(ContractError::InvalidTimelockOrder {
+            earlier: earlier.0.to_string(),
+            later: later.0.to_string(),
+        });
+    }
+    if later.1 - earlier.1 < min {
+        return Err(ContractError::TimelockWindowTooShort {
+            from: earlier.0.to_string(),
+            to: later.0.to_string(),
+            min,
+            actual: later.1 - earlier.1,
+        });
+    }
+    Ok(())
+}
+
+/// Minimum lengths in seconds of the windows between consecutive timelock stages
+#[cw_serde]
+pub struct TimelockWindows {
+    /// Private withdrawal window, from withdrawal to public withdrawal
+    pub min_withdrawal: u32,
+    /// Public withdrawal window, from public withdrawal to cancellation
+    pub min_public_withdrawal: u32,
+    /// Private cancellation window on the source chain, from cancellation to public cancellation
+    pub min_cancellation: u32,
+}
+
+impl Default for TimelockWindows {
+    /// One second windows, so that consecutive stages are strictly ordered
+    fn default() -> Self {
+        TimelockWindows {
+            min_withdrawal: 1,
+            min_public_withdrawal: 1,
+            min_cancellation: 1,
+        }
+    }
+}
+
+impl TimelockWindows {
+    /// Check that every window is at least one second and at most `rescue_delay`
+    pub fn validate(&self, rescue_delay: u32) -> Result<(), ContractError> {
+        for (window, value) in [
+            ("min_withdrawal", self.min_withdrawal),
+            ("min_public_withdrawal", self.min_public_withdrawal),
+            ("min_cancellation", self.min_cancellation),
+        ] {
+            if value == 0 || value > rescue_delay {
+                return Err(ContractError::InvalidTimelockWindow {
+                    window: window.to_string(),
+                    value,
+                    max: rescue_delay,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Timelock stages enum
 #[cw_serde]
 pub enum TimelockStage {
@@ -87,7 +283,183 @@ pub enum TimelockStage {
     RescueDelay(u32),
 }
 
+/// Settlement status of an escrow
+#[cw_serde]
+#[derive(Copy)]
+pub enum EscrowStatus {
+    Active,
+    Withdrawn,
+    Cancelled,
+    Rescued,
+}
+
+impl EscrowStatus {
+    /// Validate the transition to `next`. Withdrawal and cancellation are only
+    /// possible while active; funds can be rescued from any state.
+    pub fn transition(self, next: EscrowStatus) -> Result<EscrowStatus, ContractError> {
+        match (self, next) {
+            (EscrowStatus::Active, _) | (_, EscrowStatus::Rescued) => Ok(next),
+            (EscrowStatus::Withdrawn, _) => Err(ContractError::EscrowAlreadyWithdrawn {}),
+            (EscrowStatus::Cancelled, _) => Err(ContractError::EscrowAlreadyCancelled {}),
+            (EscrowStatus::Rescued, _) => Err(ContractError::InvalidStatusTransition {
+                from: self.to_string(),
+                to: next.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for EscrowStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let status = match self {
+            EscrowStatus::Active => "active",
+            EscrowStatus::Withdrawn => "withdrawn",
+            EscrowStatus::Cancelled => "cancelled",
+            EscrowStatus::Rescued => "rescued",
+        };
+        write!(f, "{}", status)
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct CodeChecksumResponse {
     pub checksum_hex: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_immutables_hash() {
+        let mut immutables = Immutables {
+            order_hash: "11".repeat(32),
+            hashlock: "22".repeat(32),
+            maker: "maker".to_string(),
+            taker: "taker".to_string(),
+            token: "uatom".to_string(),
+            amount: Uint256::from(1_000u128),
+            safety_deposit: Uint256::from(100u128),
+            timelocks: Timelocks {
+                deployed_at: 0,
+                src_withdrawal: 10,
+                src_public_withdrawal: 20,
+                src_cancellation: 30,
+                src_public_cancellation: 40,
+                dst_withdrawal: 10,
+                dst_public_withdrawal: 20,
+                dst_cancellation: 30,
+            },
+            parameters: b"params".to_vec(),
+            rescue_delay: None,
+        };
+        // computed by the 0.1.0 `compute_immutables_hash`
+        assert_eq!(
+            immutables.compute_legacy_immutables_hash().unwrap(),
+            "30963cb3c1280d008eba09c08b916368122f2015cb098970ffad70bd0fe67de5"
+        );
+
+        immutables.rescue_delay = Some(3600);
+        assert!(immutables.compute_legacy_immutables_hash().is_err());
+    }
+
+    #[test]
+    fn test_timelocks_packed_roundtrip() {
+        let timelocks = Timelocks {
+            deployed_at: 1_700_000_000,
+            src_withdrawal: 10,
+            src_public_withdrawal: 120,
+            src_cancellation: 121,
+            src_public_cancellation: 122,
+            dst_withdrawal: 10,
+            dst_public_withdrawal: 100,
+            dst_cancellation: 101,
+        };
+        let packed = timelocks.to_packed_u256().unwrap();
+        assert_eq!(Timelocks::from_packed_u256(packed), timelocks);
+
+        let packed = Uint256::from_be_bytes([0xab; 32]);
+        assert_eq!(Timelocks::from_packed_u256(packed).to_packed_u256().unwrap(), packed);
+
+        let overflow = Timelocks {
+            deployed_at: u32::MAX as u64 + 1,
+            ..timelocks
+        };
+        assert!(overflow.to_packed_u256().is_err());
+    }
+
+    #[test]
+    fn test_timelocks_validate() {
+        let timelocks = Timelocks {
+            deployed_at: 0,
+            src_withdrawal: 10,
+            src_public_withdrawal: 120,
+            src_cancellation: 121,
+            src_public_cancellation: 122,
+            dst_withdrawal: 10,
+            dst_public_withdrawal: 100,
+            dst_cancellation: 101,
+        };
+        assert!(timelocks.validate(&TimelockWindows::default()).is_ok());
+
+        let windows = TimelockWindows {
+            min_withdrawal: 60,
+            min_public_withdrawal: 10,
+            min_cancellation: 0,
+        };
+        assert!(matches!(
+            timelocks.validate(&windows),
+            Err(ContractError::TimelockWindowTooShort { .. })
+        ));
+
+        let zero_window = TimelockWindows {
+            min_cancellation: 0,
+            ..TimelockWindows::default()
+        };
+        assert!(matches!(
+            zero_window.validate(3600),
+            Err(ContractError::InvalidTimelockWindow { value: 0, .. })
+        ));
+        assert!(matches!(
+            windows.validate(30),
+            Err(ContractError::InvalidTimelockWindow { value: 60, max: 30, .. })
+        ));
+
+        assert!(timelocks.validate_rescue_delay(121, TimelockStage::SrcPublicCancellation).is_err());
+        assert!(timelocks.validate_rescue_delay(122, TimelockStage::SrcPublicCancellation).is_ok());
+
+        let unordered = Timelocks {
+            dst_public_withdrawal: 5,
+            ..timelocks
+        };
+        assert!(timelocks.validate_src(&TimelockWindows::default()).is_ok());
+        assert!(matches!(
+            unordered.validate_dst(&TimelockWindows::default()),
+            Err(ContractError::InvalidTimelockOrder { .. })
+        ));
+    }
+
+    #[test]
+    fn test_escrow_status_transitions() {
+        assert_eq!(
+            EscrowStatus::Active.transition(EscrowStatus::Withdrawn).unwrap(),
+            EscrowStatus::Withdrawn
+        );
+        assert_eq!(
+            EscrowStatus::Cancelled.transition(EscrowStatus::Rescued).unwrap(),
+            EscrowStatus::Rescued
+        );
+        assert!(matches!(
+            EscrowStatus::Withdrawn.transition(EscrowStatus::Withdrawn),
+            Err(ContractError::EscrowAlreadyWithdrawn {})
+        ));
+        assert!(matches!(
+            EscrowStatus::Cancelled.transition(EscrowStatus::Withdrawn),
+            Err(ContractError::EscrowAlreadyCancelled {})
+        ));
+        assert!(matches!(
+            EscrowStatus::Rescued.transition(EscrowStatus::Cancelled),
+            Err(ContractError::InvalidStatusTransition { .. })
+        ));
+    }
+}
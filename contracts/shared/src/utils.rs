@@ -1,10 +1,15 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-use cosmwasm_std::{instantiate2_address, Addr, Binary, CanonicalAddr, HexBinary, MessageInfo, QuerierWrapper, StdError, StdResult, Uint256};
+use cosmwasm_std::{instantiate2_address, Addr, Api, Binary, CanonicalAddr, HexBinary, MessageInfo, QuerierWrapper, StdError, StdResult, Uint256};
 use sha2::{Digest as Sha2Digest, Sha256};
 use sha3::Keccak256;
 
-use crate::{ContractError, Immutables};
+use crate::{hex_to_word, ContractError, Immutables};
+
+/// Shortest allowed rescue delay, one hour
+pub const MIN_RESCUE_DELAY: u32 = 3600;
+/// Longest allowed rescue delay, 30 days
+pub const MAX_RESCUE_DELAY: u32 = 30 * 86400;
 
 /// Compute Keccak256 hash (compatible with Ethereum)
 pub fn keccak256(data: &[u8]) -> String {
@@ -20,10 +25,14 @@ pub fn sha256(data: &[u8]) -> String {
     format!("{:x}", hasher.finalize())
 }
 
-/// Validate secret against hashlock
+/// Validate secret against hashlock, compared as the words they are hashed as
+/// so that `0x` prefixed or upper case hashlocks match
 pub fn validate_secret(secret: &Binary, hashlock: &str) -> bool {
     let secret_hash = keccak256(secret);
-    secret_hash == hashlock
+    match (hex_to_word(&secret_hash), hex_to_word(hashlock)) {
+        (Ok(secret_hash), Ok(hashlock)) => secret_hash == hashlock,
+        _ => false,
+    }
 }
 
 /// Check if current time is after given timelock
@@ -46,6 +55,12 @@ pub fn get_code_hash(querier: QuerierWrapper, code_id: u64) -> StdResult<HexBina
     Ok(code_info.checksum)
 }
 
+/// Whether `token` refers to a CW20 contract rather than a native denom.
+/// Native, IBC and TokenFactory denoms are never valid addresses.
+pub fn is_cw20_token(api: &dyn Api, token: &str) -> bool {
+    api.addr_validate(token).is_ok()
+}
+
 /// Validate caller is authorized
 pub fn validate_caller(caller: &Addr, authorized: &Addr) -> StdResult<()> {
     if caller != authorized {
@@ -62,89 +77,106 @@ pub fn validate_sufficient_balance(available: Uint256, required: Uint256) -> Std
     Ok(())
 }
 
-/// Validates that all required tokens are provided with sufficient amounts
+/// Validates that exactly the required tokens are provided: the escrowed
+/// amount and the safety deposit, and nothing else
 pub fn validate_token_amounts(
     immutables: &Immutables,
     info: &MessageInfo,
     safety_deposit_token: &str,
 ) -> Result<(), ContractError> {
-    let funds_map: HashMap<String, Uint256> = info.funds
-        .iter()
-        .map(|coin| (coin.denom.clone(), Uint256::from(coin.amount)))
-        .collect();
-    
+    let mut funds_map: BTreeMap<String, Uint256> = BTreeMap::new();
+    for coin in info.funds.iter() {
+        *funds_map.entry(coin.denom.clone()).or_default() += Uint256::from(coin.amount);
+    }
+
     let is_same_token = safety_deposit_token == immutables.token;
-    
-    if is_same_token {
-        let total_expected = immutables.safety_deposit + immutables.amount;
-        let actual_amount = funds_map.get(&immutables.token).copied().unwrap_or_else(Uint256::zero);
-        
-        if actual_amount < total_expected {
-            return Err(ContractError::InsufficientTokenAmount {
-                token: immutables.token.clone(),
-                expected: total_expected.to_string(),
-                actual: actual_amount.to_string(),
-            });
-        }
+
+    let expected = if is_same_token {
+        vec![(immutables.token.as_str(), immutables.safety_deposit + immutables.amount)]
     } else {
-        
-        let actual_safety_deposit_amount = funds_map
-            .get(safety_deposit_token)
-            .copied()
-            .unwrap_or_else(Uint256::zero);
-        
-        if actual_safety_deposit_amount < immutables.safety_deposit {
+        vec![
+            (safety_deposit_token, immutables.safety_deposit),
+            (immutables.token.as_str(), immutables.amount),
+        ]
+    };
+
+    for (token, expected_amount) in expected {
+        let actual_amount = funds_map.remove(token).unwrap_or_else(Uint256::zero);
+
+        if actual_amount < expected_amount {
             return Err(ContractError::InsufficientTokenAmount {
-                token: safety_deposit_token.to_string(),
-                expected: immutables.safety_deposit.to_string(),
-                actual: actual_safety_deposit_amount.to_string(),
+                token: token.to_string(),
+                expected: expected_amount.to_string(),
+                actual: actual_amount.to_string(),
             });
         }
-        
-        let actual_main_amount = funds_map
-            .get(&immutables.token)
-            .copied()
-            .unwrap_or_else(Uint256::zero);
-        
-        if actual_main_amount < immutables.amount {
-            return Err(ContractError::InsufficientTokenAmount {
-                token: immutables.token.clone(),
-                expected: immutables.amount.to_string(),
-                actual: actual_main_amount.to_string(),
+        if actual_amount > expected_amount {
+            return Err(ContractError::UnexpectedTokenAmount {
+                token: token.to_string(),
+                expected: expected_amount.to_string(),
+                actual: actual_amount.to_string(),
             });
         }
     }
-    
+
+    // any other denom would stay in the contract untracked
+    if let Some((token, actual_amount)) = funds_map.into_iter().find(|(_, amount)| !amount.is_zero()) {
+        return Err(ContractError::UnexpectedTokenAmount {
+            token,
+            expected: "0".to_string(),
+            actual: actual_amount.to_string(),
+        });
+    }
+
     Ok(())
 }
 
-/// Compute the deterministic address for an escrow contract
-/// This function extracts the core logic for computing deterministic addresses
-pub fn compute_escrow_address(
-    immutables: &Immutables, 
-    escrow_dst_code_hash: HexBinary,
-    factory_contract_address: &CanonicalAddr,
-) -> Result<String, ContractError> {
+/// Check that a rescue delay is within [`MIN_RESCUE_DELAY`, `MAX_RESCUE_DELAY`]
+pub fn validate_rescue_delay(rescue_delay: u32) -> Result<(), ContractError> {
+    if !(MIN_RESCUE_DELAY..=MAX_RESCUE_DELAY).contains(&rescue_delay) {
+        return Err(ContractError::InvalidRescueDelay {
+            min: MIN_RESCUE_DELAY,
+            max: MAX_RESCUE_DELAY,
+            actual: rescue_delay,
+        });
+    }
+    Ok(())
+}
 
+/// Salt used to instantiate an escrow: the raw bytes of its immutables hash
+pub fn compute_escrow_salt(immutables: &Immutables) -> Result<Binary, ContractError> {
     let salt = immutables.compute_immutables_hash()?;
-    
+
     // Convert salt string to bytes for instantiate2_address
     let salt_bytes = hex::decode(salt)
         .map_err(|_| ContractError::Std(cosmwasm_std::StdError::generic_err("Invalid salt format")))?;
-    
+
+    Ok(Binary::from(salt_bytes))
+}
+
+/// Compute the deterministic address for an escrow contract
+/// This matches the address of a `WasmMsg::Instantiate2` sent by the factory
+/// with the salt from `compute_escrow_salt`
+pub fn compute_escrow_address(
+    api: &dyn Api,
+    immutables: &Immutables,
+    escrow_code_hash: HexBinary,
+    factory_contract_address: &CanonicalAddr,
+) -> Result<Addr, ContractError> {
+
+    let salt = compute_escrow_salt(immutables)?;
+
     let escrow_address = instantiate2_address(
-        &escrow_dst_code_hash, 
-        factory_contract_address, 
-        &salt_bytes
+        &escrow_code_hash,
+        factory_contract_address,
+        &salt
     ).map_err(|e| ContractError::Std(cosmwasm_std::StdError::generic_err(format!("Failed to compute address: {}", e))))?;
-    
-    Ok(escrow_address.to_string())
+
+    Ok(api.addr_humanize(&escrow_address)?)
 }
 
 #[cfg(test)]
 mod tests {
-    use serde::{Serialize, Deserialize};
-    use cosmwasm_std::{to_binary, Uint128};
 
     use super::*;
     #[test]
@@ -155,5 +187,7 @@ mod tests {
 
         assert!(validate_secret(&secret, &hashlock));
         assert!(!validate_secret(&wrong_secret, &hashlock));
+        assert!(validate_secret(&secret, &format!("0x{}", hashlock.to_uppercase())));
+        assert!(!validate_secret(&secret, "not hex"));
     }
 }
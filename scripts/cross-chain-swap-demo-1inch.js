@@ -204,7 +204,7 @@ class CrossChainSwapDemo1inch {
           src_withdrawal: 120,
           src_public_withdrawal: 121,
           src_cancellation: 122,
-          src_public_cancellation: 10,
+          src_public_cancellation: 123,
           dst_withdrawal: 100,
           dst_public_withdrawal: 101,
           dst_cancellation: 3000 // 50 minutes in seconds
@@ -409,12 +409,12 @@ class CrossChainSwapDemo1inch {
           const txDetails = await cosmosClient.getTx(result.transactionHash);
           logInfo(`Transaction details retrieved, looking for escrow address...`);
           
-          // Look for instantiate events that contain the contract address
+          // The factory announces the verified escrow address in its escrow_created event
           let escrowAddress = null;
           for (const event of txDetails.events) {
-            if (event.type === 'instantiate') {
+            if (event.type === 'wasm-escrow_created') {
               for (const attr of event.attributes) {
-                if (attr.key === '_contract_address') {
+                if (attr.key === 'escrow_address') {
                   escrowAddress = attr.value;
                   break;
                 }